@@ -1,7 +1,7 @@
-use colored::Colorize;
+//! Error
+//!
 
-/// Error
-///
+use colored::Colorize;
 
 /// `eprintln` the error,
 /// and then exit program with given code.
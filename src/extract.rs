@@ -1,14 +1,19 @@
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::{fs, io};
-use std::io::BufRead;
-use crate::error::{e_exit, e_println};
-use crate::utils::{FileType, MultiFormatWriter};
-use bio::io::gff::GffType;
-use bio::io::{fasta, fastq, gff};
+use std::io::{BufRead, Write};
+use crate::error::{e_exit, e_println, ok_println};
+use crate::flatfile::{read_flat, FlatFormat};
+use crate::sam::{AlignmentReader, Region, SamRecord};
+use crate::utils::{is_mate_pair, line_wrap, mate_name, read_mate_pairs, read_seq_records, reverse_complement, Alphabet, AnnotationWriter, FileType, GeneticCode, MultiFormatWriter};
+use bio::io::gff::{GffType, Phase};
+use bio::bio_types::strand::Strand;
+use bio::io::{bed, fasta, fastq, gff};
+use bio::pattern_matching::myers::MyersBuilder;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use rayon::iter::IntoParallelRefIterator;
+use rayon::slice::ParallelSlice;
 use rayon::iter::ParallelIterator;
 
 /// Extract specific segments from biological sequence files
@@ -18,46 +23,149 @@ impl ExtractSegment {
     /// Extract sequences that match a single ID
     /// 
     /// # Arguments
-    /// * `paths` - Input sequence files (FASTA, FASTQ, GFF)
-    /// * `id` - Sequence identifier to extract
+    /// * `paths` - Input sequence files (FASTA, FASTQ, GFF, SAM, BAM)
+    /// * `id` - Sequence identifier (or read name) to extract
     /// * `output` - Output file path
     /// * `start` - Optional start position (0-based) for the extracted segment
     /// * `end` - Optional end position (0-based, exclusive) for the extracted segment
-    pub fn extract_id(paths: Vec<PathBuf>, id: String, output: PathBuf, start: Option<usize>, end: Option<usize>) {
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract_id(paths: Vec<PathBuf>, id: String, output: PathBuf, start: Option<usize>, end: Option<usize>, line_width: usize) {
         let id_set = vec![Self::normalize_id(&id)].into_iter().collect();
-        Self::process_files_parallel(paths, &id_set, &output, start, end)
+        Self::process_files_parallel(paths, &id_set, &output, start, end, line_width)
     }
 
     /// Extract sequences matching IDs from a file
     /// 
     /// # Arguments
-    /// * `paths` - Input sequence files (FASTA, FASTQ, GFF)
+    /// * `paths` - Input sequence files (FASTA, FASTQ, GFF, SAM, BAM)
     /// * `id_file` - File containing IDs to extract (one per line)
     /// * `output` - Output file path
     /// * `start` - Optional start position (0-based) for the extracted segment
     /// * `end` - Optional end position (0-based, exclusive) for the extracted segment
-    pub fn extract_id_files(paths: Vec<PathBuf>, id_file: PathBuf, output: PathBuf, start: Option<usize>, end: Option<usize>) {
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract_id_files(paths: Vec<PathBuf>, id_file: PathBuf, output: PathBuf, start: Option<usize>, end: Option<usize>, line_width: usize) {
         let id_set = match Self::load_id_set(&id_file) {
             Ok(set) => set,
             Err(e) => e_exit("ID-LOAD", &format!("Failed to load IDs: {}", e), 1),
         };
-        Self::process_files_parallel(paths, &id_set, &output, start, end)
+        Self::process_files_parallel(paths, &id_set, &output, start, end, line_width)
+    }
+
+    /// Extract the SAM/BAM reads overlapping a reference region
+    ///
+    /// Only primary alignments are written, as FASTA when `output` has a FASTA extension
+    /// and as FASTQ otherwise; reverse-strand reads are restored to their sequenced orientation.
+    ///
+    /// # Arguments
+    /// * `paths` - Input alignment files (SAM, BAM)
+    /// * `region` - Region as `name[:start[-end]]`, 1-based and inclusive
+    /// * `output` - Output file path
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract_region(paths: Vec<PathBuf>, region: String, output: PathBuf, line_width: usize) {
+        let region = Region::parse(&region).unwrap_or_else(|e| e_exit("ARGS", &e, 1));
+        let writer = match MultiFormatWriter::new(&output, line_width) {
+            Ok(w) => Arc::new(Mutex::new(w)),
+            Err(e) => e_exit("WRITER", &format!("Output init failed: {}", e), 2),
+        };
+        let fasta = FileType::infer_file_type(&output) == FileType::Fasta;
+
+        paths.par_iter().for_each(|path| match FileType::infer_file_type(path) {
+            FileType::Sam | FileType::Bam => Self::process_file(path, &HashSet::new(), Arc::clone(&writer), |p, _, w| {
+                Self::process_alignments(p, |record| region.overlaps(record), w, fasta)
+            }),
+            _ => e_println("TYPE-ERROR", &format!("Region extraction needs SAM/BAM input: {:?}", path)),
+        });
+    }
+
+    /// Extract both mates of the read pairs matching a single ID
+    ///
+    /// # Arguments
+    /// * `paths` - Mate files (FASTA, FASTQ) given as R1,R2 pairs
+    /// * `id` - Read name to extract, with or without a `/1` `/2` suffix
+    /// * `out_dir` - Output directory, mates are written under their input file names
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract_id_paired(paths: Vec<PathBuf>, id: String, out_dir: PathBuf, line_width: usize) {
+        let id_set = vec![mate_name(&Self::normalize_id(&id)).to_string()].into_iter().collect();
+        Self::process_pairs(paths, &id_set, &out_dir, line_width)
+    }
+
+    /// Extract both mates of the read pairs matching IDs from a file
+    ///
+    /// # Arguments
+    /// * `paths` - Mate files (FASTA, FASTQ) given as R1,R2 pairs
+    /// * `id_file` - File containing read names to extract (one per line)
+    /// * `out_dir` - Output directory, mates are written under their input file names
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract_id_files_paired(paths: Vec<PathBuf>, id_file: PathBuf, out_dir: PathBuf, line_width: usize) {
+        let id_set = match Self::load_id_set(&id_file) {
+            Ok(set) => set.iter().map(|id| mate_name(id).to_string()).collect(),
+            Err(e) => e_exit("ID-LOAD", &format!("Failed to load IDs: {}", e), 1),
+        };
+        Self::process_pairs(paths, &id_set, &out_dir, line_width)
+    }
+
+    /// Process mate files pair by pair, writing both mates when either matches
+    fn process_pairs(paths: Vec<PathBuf>, id_set: &HashSet<String>, out_dir: &Path, line_width: usize) {
+        if paths.len() % 2 != 0 {
+            e_exit("PAIR-ERROR", "Paired extraction needs an even number of files (R1,R2 pairs)", 1);
+        }
+        if let Err(e) = fs::create_dir_all(out_dir) {
+            e_exit("WRITER", &format!("Output init failed: {}", e), 2);
+        }
+
+        paths.par_chunks(2).for_each(|pair| {
+            let (r1, r2) = (&pair[0], &pair[1]);
+            let result = (|| {
+                let create = |path: &PathBuf| {
+                    MultiFormatWriter::new(&out_dir.join(path.file_name().unwrap_or_default()), line_width)
+                        .map_err(|e| format!("Output init failed: {}", e))
+                };
+                let (mut writer1, mut writer2) = (create(r1)?, create(r2)?);
+
+                for (n, mates) in read_mate_pairs(r1, r2)?.enumerate() {
+                    let (a, b) = mates?;
+                    if !is_mate_pair(&a, &b) {
+                        return Err(format!("Mates out of sync at pair {} ({} / {})", n + 1, a.id, b.id));
+                    }
+                    let matches = |id: &str| id_set.contains(mate_name(&Self::normalize_id(id)));
+                    if matches(&a.id) || matches(&b.id) {
+                        writer1.write_record(&a)
+                            .and_then(|_| writer2.write_record(&b))
+                            .map_err(|e| format!("Write failed: {}", e))?;
+                    }
+                }
+                writer1.flush()
+                    .and_then(|_| writer2.flush())
+                    .map_err(|e| format!("Write failed: {}", e))
+            })();
+
+            if let Err(e) = result {
+                e_println("PAIR-ERROR", &format!("{} + {}: {}", r1.display(), r2.display(), e));
+            }
+        });
     }
 
     /// Process multiple files in parallel
-    fn process_files_parallel(paths: Vec<PathBuf>, id_set: &HashSet<String>, output: &PathBuf, start: Option<usize>, end: Option<usize>) {
-        let writer = match MultiFormatWriter::new(output) {
+    fn process_files_parallel(paths: Vec<PathBuf>, id_set: &HashSet<String>, output: &PathBuf, start: Option<usize>, end: Option<usize>, line_width: usize) {
+        let writer = match MultiFormatWriter::new(output, line_width) {
             Ok(w) => Arc::new(Mutex::new(w)),
             Err(e) => e_exit("WRITER", &format!("Output init failed: {}", e), 2),
         };
+        // Reads from SAM/BAM files are written as FASTA only when asked for by the extension
+        let fasta = FileType::infer_file_type(output) == FileType::Fasta;
 
         paths.par_iter().for_each(|path| {
             let writer = Arc::clone(&writer);
             match FileType::infer_file_type(path) {
                 FileType::Fasta => Self::process_file(path, id_set, writer, |p, ids, w| Self::process_fasta(p, ids, w, start, end)),
-                FileType::Gff => Self::process_file(path, id_set, writer, |p, ids, w| Self::process_gff(p, ids, w)),
-                FileType::Fastq => Self::process_file(path, id_set, writer, |p, ids, w| Self::process_fastq(p, ids, w)),
-                FileType::Unknown => e_println("TYPE-ERROR", &format!("Unsupported format: {:?}", path)),
+                FileType::Gff => Self::process_file(path, id_set, writer, Self::process_gff),
+                FileType::Fastq => Self::process_file(path, id_set, writer, Self::process_fastq),
+                FileType::Sam | FileType::Bam => Self::process_file(path, id_set, writer, |p, ids, w| {
+                    Self::process_alignments(p, |record| {
+                        ids.contains(&Self::normalize_id(&record.qname)) || ids.contains(&Self::normalize_id(&record.read_name()))
+                    }, w, fasta)
+                }),
+                _ => e_println("TYPE-ERROR", &format!("Unsupported format: {:?}", path)),
             };
         });
     }
@@ -109,11 +217,11 @@ impl ExtractSegment {
     /// Process FASTA format files to extract matching sequences
     fn process_fasta(path: &PathBuf, ids: &HashSet<String>, writer: &mut MultiFormatWriter, start: Option<usize>, end: Option<usize>) {
         let reader = fasta::Reader::from_file(path)
-            .expect(&format!("Failed to open FASTA file: {}", path.display()));
+            .unwrap_or_else(|_| panic!("Failed to open FASTA file: {}", path.display()));
             
         for record in reader.records() {
             let record = record
-                .expect(&format!("Failed to parse FASTA record in {}", path.display()));
+                .unwrap_or_else(|_| panic!("Failed to parse FASTA record in {}", path.display()));
                 
             if ids.contains(&Self::normalize_id(record.id())) {
                 // Apply start and end positions if specified
@@ -149,11 +257,11 @@ impl ExtractSegment {
                     let segment_record = fasta::Record::with_attrs(record.id(), description.as_deref(), &segment_seq);
                     
                     writer.fa.write_record(&segment_record)
-                        .expect(&format!("Failed to write FASTA record segment: {}", record.id()));
+                        .unwrap_or_else(|_| panic!("Failed to write FASTA record segment: {}", record.id()));
                 } else {
                     // Write the complete record if no positions specified
                     writer.fa.write_record(&record)
-                        .expect(&format!("Failed to write FASTA record: {}", record.id()));
+                        .unwrap_or_else(|_| panic!("Failed to write FASTA record: {}", record.id()));
                 }
             }
         }
@@ -162,38 +270,75 @@ impl ExtractSegment {
     /// Process GFF format files to extract matching annotations
     fn process_gff(path: &PathBuf, ids: &HashSet<String>, writer: &mut MultiFormatWriter) {
         let mut reader = gff::Reader::from_file(path, GffType::GFF3)
-            .expect(&format!("Failed to open GFF file: {}", path.display()));
+            .unwrap_or_else(|_| panic!("Failed to open GFF file: {}", path.display()));
             
         for record in reader.records() {
             let record = record
-                .expect(&format!("Failed to parse GFF record in {}", path.display()));
+                .unwrap_or_else(|_| panic!("Failed to parse GFF record in {}", path.display()));
                 
             if let Some(id) = record.attributes().get("ID") {
                 if ids.contains(&Self::normalize_id(id)) {
                     writer.gff.write(&record)
-                        .expect(&format!("Failed to write GFF record: {}", id));
+                        .unwrap_or_else(|_| panic!("Failed to write GFF record: {}", id));
                 }
             }
         }
     }
 
+    /// Process SAM/BAM files, writing the primary alignments accepted by `select` as reads
+    ///
+    /// Reads without qualities get `I` (Phred 40) in FASTQ output.
+    fn process_alignments<S>(path: &PathBuf, select: S, writer: &mut MultiFormatWriter, fasta: bool)
+    where
+        S: Fn(&SamRecord) -> bool,
+    {
+        let result = (|| -> Result<(), String> {
+            let reader = AlignmentReader::from_file(path)
+                .map_err(|e| format!("Failed to open alignment file: {}", e))?;
+            for record in reader.records() {
+                let record = record?;
+                if !record.is_primary() || !select(&record) {
+                    continue;
+                }
+                let written = if fasta {
+                    writer.fa.write_record(&record.to_fasta())
+                } else {
+                    writer.fq.write_record(&record.to_fastq(b'I'))
+                };
+                written.map_err(|e| format!("Write failed: {}", e))?;
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            e_println("SAM-ERROR", &format!("{}: {}", path.display(), e));
+        }
+    }
+
     /// Process FASTQ format files to extract matching sequences
     fn process_fastq(path: &PathBuf, ids: &HashSet<String>, writer: &mut MultiFormatWriter) {
         let reader = fastq::Reader::from_file(path)
-            .expect(&format!("Failed to open FASTQ file: {}", path.display()));
+            .unwrap_or_else(|_| panic!("Failed to open FASTQ file: {}", path.display()));
             
         for record in reader.records() {
             let record = record
-                .expect(&format!("Failed to parse FASTQ record in {}", path.display()));
+                .unwrap_or_else(|_| panic!("Failed to parse FASTQ record in {}", path.display()));
                 
             if ids.contains(&Self::normalize_id(record.id())) {
                 writer.fq.write_record(&record)
-                    .expect(&format!("Failed to write FASTQ record: {}", record.id()));
+                    .unwrap_or_else(|_| panic!("Failed to write FASTQ record: {}", record.id()));
             }
         }
     }
 }
 
+/// Output format of [`ExtractExplain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    Fasta, // Feature sequences
+    Bed,   // Feature coordinates (BED6), e.g. for bedtools
+}
+
 /// Extract and explain annotated sequence features
 pub struct ExtractExplain;
 
@@ -201,11 +346,16 @@ impl ExtractExplain {
     /// Extract annotated features from sequences
     /// 
     /// # Arguments
-    /// * `seq_files` - FASTA files containing sequences
-    /// * `anno_files` - GFF files containing annotations
+    /// * `seq_files` - FASTA files containing sequences, or GenBank/EMBL files whose own
+    ///   feature tables are annotations too
+    /// * `anno_files` - GFF or GenBank/EMBL files containing annotations, may be empty for
+    ///   GenBank/EMBL sequence files
     /// * `output` - Output directory for extracted features
     /// * `feature_types` - Optional set of feature types to extract (e.g., "CDS", "gene")
-    pub fn extract(seq_files: Vec<PathBuf>, anno_files: Vec<PathBuf>, output: PathBuf, feature_types: Option<Vec<String>>) {
+    /// * `format` - Write the feature sequences (as `{file stem}.fa` for GenBank/EMBL inputs),
+    ///   or their coordinates as `{file stem}.bed`
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract(seq_files: Vec<PathBuf>, anno_files: Vec<PathBuf>, output: PathBuf, feature_types: Option<Vec<String>>, format: ExplainFormat, line_width: usize) {
         // Create output directory
         fs::create_dir_all(&output).unwrap_or_else(|e| {
             e_exit("FS", &format!("Failed to create output directory: {}", e), 1);
@@ -213,38 +363,53 @@ impl ExtractExplain {
 
         // Process each sequence file in parallel
         seq_files.par_iter().for_each(|seq_path| {
-            // Load sequence data
-            let seq_data = Self::load_sequences(seq_path)
+            // Load sequence data, with the features of GenBank/EMBL files
+            let (seq_data, embedded) = Self::load_sequences(seq_path)
                 .unwrap_or_else(|e| e_exit("SEQ-LOAD", &e, 2));
 
             // Process all annotation files
-            let annotations: Vec<_> = anno_files.par_iter()
-                .flat_map(|anno_path| {
-                    let anns = Self::load_annotations(anno_path)
-                        .unwrap_or_else(|e| e_exit("ANN-LOAD", &e, 3));
-                    
-                    // Filter annotations by feature type if specified
-                    if let Some(types) = &feature_types {
-                        anns.into_iter()
-                            .filter(|ann| types.iter().any(|t| t.eq_ignore_ascii_case(ann.feature_type())))
-                            .collect::<Vec<_>>()
-                    } else {
-                        anns
-                    }
-                })
+            let loaded: Vec<_> = anno_files.par_iter()
+                .flat_map(|anno_path| Self::load_annotations(anno_path)
+                    .unwrap_or_else(|e| e_exit("ANN-LOAD", &e, 3)))
+                .collect();
+            let annotations: Vec<_> = embedded.into_iter()
+                .chain(loaded)
+                // Filter annotations by feature type if specified
+                .filter(|ann| feature_types.as_ref()
+                    .is_none_or(|types| types.iter().any(|t| t.eq_ignore_ascii_case(ann.feature_type()))))
                 .collect();
 
             // Generate result file
-            let output_path = output.join(seq_path.file_name().unwrap());
-            Self::generate_annotated_file(&seq_data, &annotations, &output_path)
-                .unwrap_or_else(|e| e_exit("OUTPUT", &e, 4));
+            let result = match format {
+                ExplainFormat::Fasta => {
+                    // GenBank/EMBL inputs give FASTA output, so they get a FASTA extension
+                    let output_path = match FlatFormat::of_path(seq_path) {
+                        Some(_) => output.join(seq_path.file_stem().unwrap()).with_extension("fa"),
+                        None => output.join(seq_path.file_name().unwrap()),
+                    };
+                    Self::generate_annotated_file(&seq_data, &annotations, &output_path, line_width)
+                }
+                ExplainFormat::Bed => {
+                    let output_path = output.join(seq_path.file_stem().unwrap()).with_extension("bed");
+                    Self::generate_bed_file(&seq_data, &annotations, &output_path)
+                }
+            };
+            result.unwrap_or_else(|e| e_exit("OUTPUT", &e, 4));
         });
     }
 
     /// Load sequence data into memory (suitable for small to medium files)
     /// 
-    /// Returns a map of sequence IDs to FASTA records
-    fn load_sequences(path: &Path) -> Result<HashMap<String, fasta::Record>, String> {
+    /// Returns a map of sequence IDs to FASTA records, and the feature tables of
+    /// GenBank/EMBL files as GFF records (empty for FASTA files)
+    fn load_sequences(path: &Path) -> Result<(HashMap<String, fasta::Record>, Vec<gff::Record>), String> {
+        if let Some(format) = FlatFormat::of_path(path) {
+            let records = read_flat(path, format)?;
+            let features = records.iter().flat_map(|r| r.gff_records(format)).collect();
+            let seq_map = records.iter().map(|r| (r.name.clone(), r.to_fasta())).collect();
+            return Ok((seq_map, features));
+        }
+
         let reader = fasta::Reader::from_file(path)
             .map_err(|e| format!("Failed to read sequence file: {} - {}", path.display(), e))?;
 
@@ -253,13 +418,16 @@ impl ExtractExplain {
             let record = record.map_err(|e| format!("Failed to parse FASTA: {}", e))?;
             seq_map.insert(record.id().to_string(), record);
         }
-        Ok(seq_map)
+        Ok((seq_map, Vec::new()))
     }
 
-    /// Load GFF annotations
+    /// Load GFF annotations, or the feature tables of a GenBank/EMBL file
     /// 
     /// Returns a vector of GFF records
     fn load_annotations(path: &Path) -> Result<Vec<gff::Record>, String> {
+        if let Some(format) = FlatFormat::of_path(path) {
+            return Ok(read_flat(path, format)?.iter().flat_map(|r| r.gff_records(format)).collect());
+        }
         let mut reader = gff::Reader::from_file(path, GffType::GFF3)
             .map_err(|e| format!("Failed to read annotation file: {} - {}", path.display(), e))?;
 
@@ -274,10 +442,12 @@ impl ExtractExplain {
     fn generate_annotated_file(
         seq_data: &HashMap<String, fasta::Record>,
         annotations: &[gff::Record],
-        output: &Path
+        output: &Path,
+        line_width: usize,
     ) -> Result<(), String> {
         let mut writer = fasta::Writer::new(File::create(output)
             .map_err(|e| format!("Failed to create output file: {} - {}", output.display(), e))?);
+        writer.set_linewrap(line_wrap(line_width));
 
         // Generate feature sequences for each annotation
         for ann in annotations {
@@ -311,6 +481,44 @@ impl ExtractExplain {
         Ok(())
     }
 
+    /// Generate BED files of the feature coordinates
+    ///
+    /// Writes one BED6 line per annotation on a known sequence: 0-based start,
+    /// the `ID` attribute (or feature type) as name, the GFF score (0 if missing) and strand.
+    fn generate_bed_file(
+        seq_data: &HashMap<String, fasta::Record>,
+        annotations: &[gff::Record],
+        output: &Path
+    ) -> Result<(), String> {
+        let mut writer = bed::Writer::to_file(output)
+            .map_err(|e| format!("Failed to create output file: {} - {}", output.display(), e))?;
+
+        for ann in annotations {
+            let Some(seq) = seq_data.get(ann.seqname()) else {
+                e_println("ANN-SKIP", &format!("Sequence not found: {}", ann.seqname()));
+                continue;
+            };
+            // Same coordinate check as the sequence output
+            Self::extract_feature(seq, ann)
+                .map_err(|e| format!("Failed to extract feature: {}", e))?;
+
+            let mut record = bed::Record::new();
+            record.set_chrom(ann.seqname());
+            record.set_start(ann.start().saturating_sub(1));
+            record.set_end(*ann.end());
+            record.set_name(ann.attributes().get("ID").map_or(ann.feature_type(), String::as_str));
+            record.set_score(ann.score().map_or("0".to_string(), |s| s.to_string()).as_str());
+            record.push_aux(match ann.strand() {
+                Some(Strand::Forward) => "+",
+                Some(Strand::Reverse) => "-",
+                _ => ".",
+            });
+            writer.write(&record)
+                .map_err(|e| format!("Write failed: {}", e))?;
+        }
+        Ok(())
+    }
+
     /// Extract sequence segment for a feature
     /// 
     /// Extracts the subsequence corresponding to the annotation coordinates
@@ -328,3 +536,628 @@ impl ExtractExplain {
         Ok(seq.seq()[start as usize..end as usize].to_vec())
     }
 }
+
+/// How a motif pattern is matched against sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotifKind {
+    Exact, // Literal sequence, case-insensitive
+    Iupac, // IUPAC nucleotide codes, e.g. `R` matches `A`, `G` and `R`
+    Regex, // Regular expression, case-insensitive
+}
+
+/// Output format of motif hits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotifFormat {
+    Bed,  // BED6, 0-based half-open
+    Gff3, // GFF3 `sequence_motif` features, 1-based closed
+}
+
+/// A named motif, compiled for exact or approximate matching
+pub struct Motif {
+    pub name: String,
+    matcher: Matcher,
+}
+
+/// Search strategy of a [`Motif`]
+enum Matcher {
+    Regex(regex::bytes::Regex),
+    // Substitutions only, `allowed` lists the sequence bytes accepted at each motif position
+    Mismatch { allowed: Vec<[bool; 256]>, max: usize },
+    // Substitutions and indels, with Myers' bit-parallel algorithm
+    Edit { pattern: Vec<u8>, iupac: bool, max: u8 },
+}
+
+impl Motif {
+    /// Longest motif supported with indels, the bit vector size of `Myers<u64>`
+    pub const MAX_EDIT_LEN: usize = 64;
+    /// Sequence codes covered by each IUPAC nucleotide code
+    const IUPAC_CODES: &'static [u8] = b"ACGTURYKMSWBDHVN";
+
+    /// Compiles a motif pattern
+    ///
+    /// # Arguments
+    /// * `name` - Name reported with the hits
+    /// * `pattern` - Motif sequence or regular expression
+    /// * `kind` - How the pattern is matched
+    pub fn new(name: &str, pattern: &str, kind: MotifKind) -> Result<Self, String> {
+        let expression = match kind {
+            MotifKind::Exact => regex::escape(pattern),
+            MotifKind::Iupac => pattern.bytes()
+                .map(|c| Self::iupac_covered(c)
+                    .map(|codes| format!("[{}]", String::from_utf8_lossy(&codes)))
+                    .ok_or_else(|| Self::invalid_code(c, pattern)))
+                .collect::<Result<String, String>>()?,
+            MotifKind::Regex => pattern.to_string(),
+        };
+        let regex = regex::bytes::Regex::new(&format!("(?i){}", expression))
+            .map_err(|e| format!("Invalid motif {}: {}", pattern, e))?;
+        Ok(Self { name: name.to_string(), matcher: Matcher::Regex(regex) })
+    }
+
+    /// Compiles a motif matched with up to `max_distance` differences
+    ///
+    /// Without `indels` only substitutions are allowed and every alignment is reported;
+    /// with `indels` the edit distance is used and overlapping hits are reduced to the best one.
+    ///
+    /// # Arguments
+    /// * `name` - Name reported with the hits
+    /// * `pattern` - Motif sequence, exact or IUPAC
+    /// * `kind` - `MotifKind::Exact` or `MotifKind::Iupac`
+    /// * `max_distance` - Maximum number of mismatches (or edits with `indels`)
+    /// * `indels` - Also allow insertions and deletions
+    pub fn approximate(name: &str, pattern: &str, kind: MotifKind, max_distance: usize, indels: bool) -> Result<Self, String> {
+        if kind == MotifKind::Regex {
+            return Err("Approximate search needs an exact or IUPAC motif, not a regex".to_string());
+        }
+        if pattern.is_empty() {
+            return Err("Empty motif".to_string());
+        }
+        let iupac = kind == MotifKind::Iupac;
+        let pattern = pattern.to_ascii_uppercase().into_bytes();
+
+        let matcher = if indels {
+            if pattern.len() > Self::MAX_EDIT_LEN {
+                return Err(format!("Motifs with indels are limited to {} bases", Self::MAX_EDIT_LEN));
+            }
+            if let Some(&c) = pattern.iter().find(|&&c| iupac && Self::iupac_covered(c).is_none()) {
+                return Err(Self::invalid_code(c, &String::from_utf8_lossy(&pattern)));
+            }
+            Matcher::Edit { pattern, iupac, max: max_distance.min(u8::MAX as usize) as u8 }
+        } else {
+            let allowed = pattern.iter()
+                .map(|&c| {
+                    let codes = if iupac {
+                        Self::iupac_covered(c).ok_or_else(|| Self::invalid_code(c, &String::from_utf8_lossy(&pattern)))?
+                    } else {
+                        vec![c]
+                    };
+                    let mut allowed = [false; 256];
+                    for code in codes {
+                        allowed[code.to_ascii_uppercase() as usize] = true;
+                        allowed[code.to_ascii_lowercase() as usize] = true;
+                    }
+                    Ok(allowed)
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            Matcher::Mismatch { allowed, max: max_distance }
+        };
+        Ok(Self { name: name.to_string(), matcher })
+    }
+
+    fn invalid_code(code: u8, pattern: &str) -> String {
+        format!("Invalid IUPAC code {:?} in motif {}", code as char, pattern)
+    }
+
+    /// Sequence codes covered by an IUPAC code, so an ambiguous sequence base
+    /// only matches a code that includes all its bases
+    fn iupac_covered(code: u8) -> Option<Vec<u8>> {
+        let bases = |c: u8| -> Option<u8> {
+            Some(match c.to_ascii_uppercase() {
+                b'A' => 0b0001,
+                b'C' => 0b0010,
+                b'G' => 0b0100,
+                b'T' | b'U' => 0b1000,
+                b'R' => 0b0101,
+                b'Y' => 0b1010,
+                b'K' => 0b1100,
+                b'M' => 0b0011,
+                b'S' => 0b0110,
+                b'W' => 0b1001,
+                b'B' => 0b1110,
+                b'D' => 0b1101,
+                b'H' => 0b1011,
+                b'V' => 0b0111,
+                b'N' => 0b1111,
+                _ => return None,
+            })
+        };
+        let covered = bases(code)?;
+        Some(Self::IUPAC_CODES.iter()
+            .copied()
+            .filter(|&c| bases(c).is_some_and(|b| b & !covered == 0))
+            .collect())
+    }
+
+    /// Finds all hits of the motif in a sequence, overlapping ones included
+    ///
+    /// Reverse-strand hits are found on the reverse complement and reported in forward coordinates.
+    pub fn find(&self, seq_id: &str, seq: &[u8], both_strands: bool) -> Vec<MotifHit> {
+        let mut hits = self.find_strand(seq_id, seq, '+');
+        if both_strands {
+            let revcomp = reverse_complement(seq);
+            hits.extend(self.find_strand(seq_id, &revcomp, '-').into_iter().map(|mut hit| {
+                (hit.start, hit.end) = (seq.len() - hit.end, seq.len() - hit.start);
+                hit
+            }));
+            hits.sort_by_key(|hit| (hit.start, hit.end));
+        }
+        hits
+    }
+
+    fn find_strand(&self, seq_id: &str, seq: &[u8], strand: char) -> Vec<MotifHit> {
+        let hit = |start: usize, end: usize, distance: usize| MotifHit {
+            seq_id: seq_id.to_string(),
+            start,
+            end,
+            strand,
+            motif: self.name.clone(),
+            matched: seq[start..end].to_vec(),
+            distance,
+        };
+
+        match &self.matcher {
+            Matcher::Regex(regex) => {
+                let mut hits = Vec::new();
+                let mut from = 0;
+                while from < seq.len() {
+                    let Some(m) = regex.find_at(seq, from) else { break };
+                    if m.end() > m.start() {
+                        hits.push(hit(m.start(), m.end(), 0));
+                    }
+                    from = m.start() + 1;
+                }
+                hits
+            }
+            Matcher::Mismatch { allowed, max } => {
+                if seq.len() < allowed.len() {
+                    return Vec::new();
+                }
+                (0..=seq.len() - allowed.len())
+                    .filter_map(|start| {
+                        let mut mismatches = 0;
+                        for (allowed, &c) in allowed.iter().zip(&seq[start..]) {
+                            if !allowed[c as usize] {
+                                mismatches += 1;
+                                if mismatches > *max {
+                                    return None;
+                                }
+                            }
+                        }
+                        Some(hit(start, start + allowed.len(), mismatches))
+                    })
+                    .collect()
+            }
+            Matcher::Edit { pattern, iupac, max } => {
+                let mut builder = MyersBuilder::new();
+                builder.ambig(b'T', b"U").ambig(b'U', b"T");
+                if *iupac {
+                    for &code in Self::IUPAC_CODES {
+                        builder.ambig(code, Self::iupac_covered(code).unwrap_or_default());
+                    }
+                }
+                let mut myers = builder.build_64(pattern.iter());
+                let text = seq.to_ascii_uppercase();
+
+                // Every end position within the distance is a match, keep the best of each overlapping run
+                let mut hits: Vec<MotifHit> = Vec::new();
+                for (start, end, distance) in myers.find_all(text.iter(), *max) {
+                    let distance = distance as usize;
+                    match hits.last_mut() {
+                        Some(last) if start < last.end => {
+                            if distance < last.distance {
+                                *last = hit(start, end, distance);
+                            }
+                        }
+                        _ => hits.push(hit(start, end, distance)),
+                    }
+                }
+                hits
+            }
+        }
+    }
+}
+
+/// One motif hit, in 0-based half-open forward-strand coordinates
+#[derive(Debug, Clone)]
+pub struct MotifHit {
+    pub seq_id: String,
+    pub start: usize,
+    pub end: usize,
+    pub strand: char,
+    pub motif: String,
+    pub matched: Vec<u8>, // Matched sequence, on the hit strand
+    pub distance: usize,  // Mismatches or edits of approximate hits, 0 otherwise
+}
+
+/// Find motifs in sequence files
+pub struct ExtractMotif;
+
+impl ExtractMotif {
+    /// Searches motifs in every record and writes the hits as BED or GFF3
+    ///
+    /// Records of each file are searched in parallel, hits are written in record order.
+    ///
+    /// # Arguments
+    /// * `paths` - Input sequence files (FASTA, FASTQ)
+    /// * `motifs` - Compiled motifs, see [`Motif::new`]
+    /// * `output` - Output BED/GFF3 file
+    /// * `format` - Output format of the hits
+    /// * `both_strands` - Also search the reverse strand
+    /// * `flank` - If set, the hit sequences with this many flanking bases are written to `{output}.fa`
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract(paths: Vec<PathBuf>, motifs: &[Motif], output: PathBuf, format: MotifFormat, both_strands: bool, flank: Option<usize>, line_width: usize) {
+        let result = (|| -> Result<usize, String> {
+            let mut writer = HitWriter::new(&output, format, "sequence_motif", "motif")?;
+            let mut fasta_writer = match flank {
+                Some(_) => {
+                    let path = PathBuf::from(format!("{}.fa", output.display()));
+                    let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+                    let mut fasta_writer = fasta::Writer::new(file);
+                    fasta_writer.set_linewrap(line_wrap(line_width));
+                    Some(fasta_writer)
+                }
+                None => None,
+            };
+
+            let mut total = 0;
+            for path in &paths {
+                let records = read_seq_records(path)?.collect::<Result<Vec<_>, String>>()?;
+                let hits: Vec<Vec<MotifHit>> = records.par_iter()
+                    .map(|record| {
+                        let mut hits: Vec<MotifHit> = motifs.iter()
+                            .flat_map(|motif| motif.find(&record.id, &record.seq, both_strands))
+                            .collect();
+                        hits.sort_by_key(|hit| (hit.start, hit.end));
+                        hits
+                    })
+                    .collect();
+
+                let count: usize = hits.iter().map(Vec::len).sum();
+                ok_println("Motif", &format!("{} ({} hits)", path.display(), count));
+                total += count;
+
+                for (record, hits) in records.iter().zip(hits) {
+                    for (n, hit) in hits.iter().enumerate() {
+                        writer.write(hit, n + 1)?;
+                        if let (Some(flank), Some(fasta_writer)) = (flank, fasta_writer.as_mut()) {
+                            let (start, end) = (hit.start.saturating_sub(flank), (hit.end + flank).min(record.seq.len()));
+                            let segment = &record.seq[start..end];
+                            let segment = if hit.strand == '-' { reverse_complement(segment) } else { segment.to_vec() };
+                            let id = format!("{}:{}-{}({})", hit.seq_id, start, end, hit.strand);
+                            fasta_writer.write(&id, Some(&format!("motif={}", hit.motif)), &segment)
+                                .map_err(|e| format!("Write failed: {}", e))?;
+                        }
+                    }
+                }
+            }
+            writer.flush()?;
+            if let Some(fasta_writer) = fasta_writer.as_mut() {
+                fasta_writer.flush().map_err(|e| format!("Write failed: {}", e))?;
+            }
+            Ok(total)
+        })();
+
+        match result {
+            Ok(total) => ok_println("MOTIF_COMPLETE", &format!("{} hits", total)),
+            Err(e) => e_exit("MOTIF", &e, 1),
+        }
+    }
+}
+
+/// In-silico PCR with primer pairs
+pub struct ExtractPcr;
+
+impl ExtractPcr {
+    /// Finds the products of a primer pair and writes the amplicons as FASTA
+    ///
+    /// A product is a forward primer hit and a downstream reverse primer hit on the opposite
+    /// strand, at most `max_size` bases apart (primers included). Products templated by the
+    /// reverse strand are reverse-complemented, so every amplicon reads from the forward primer.
+    ///
+    /// # Arguments
+    /// * `paths` - Template sequence files (FASTA, FASTQ)
+    /// * `forward` - Forward primer, 5' to 3'
+    /// * `reverse` - Reverse primer, 5' to 3' on the opposite strand
+    /// * `max_size` - Maximum amplicon size
+    /// * `output` - Output FASTA file of the amplicons
+    /// * `bed` - Optional BED file of the amplicon coordinates, the score is the summed primer distance
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn amplify(paths: Vec<PathBuf>, forward: &Motif, reverse: &Motif, max_size: usize, output: PathBuf, bed: Option<PathBuf>, line_width: usize) {
+        let result = (|| -> Result<usize, String> {
+            let file = File::create(&output).map_err(|e| format!("Failed to create {}: {}", output.display(), e))?;
+            let mut writer = fasta::Writer::new(file);
+            writer.set_linewrap(line_wrap(line_width));
+            let mut bed_writer = match &bed {
+                Some(path) => Some(HitWriter::new(path, MotifFormat::Bed, "PCR_product", "amplicon")?),
+                None => None,
+            };
+
+            let mut total = 0;
+            for path in &paths {
+                let records = read_seq_records(path)?.collect::<Result<Vec<_>, String>>()?;
+                let products: Vec<Vec<MotifHit>> = records.par_iter()
+                    .map(|record| Self::products(&record.id, &record.seq, forward, reverse, max_size))
+                    .collect();
+
+                let count: usize = products.iter().map(Vec::len).sum();
+                ok_println("PCR", &format!("{} ({} amplicons)", path.display(), count));
+                total += count;
+
+                for (n, product) in products.iter().flatten().enumerate() {
+                    let id = format!("{}:{}-{}({})", product.seq_id, product.start, product.end, product.strand);
+                    let desc = format!("{} length={} distance={}", product.motif, product.matched.len(), product.distance);
+                    writer.write(&id, Some(&desc), &product.matched)
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                    if let Some(bed_writer) = bed_writer.as_mut() {
+                        bed_writer.write(product, n + 1)?;
+                    }
+                }
+            }
+            writer.flush().map_err(|e| format!("Write failed: {}", e))?;
+            if let Some(bed_writer) = bed_writer.as_mut() {
+                bed_writer.flush()?;
+            }
+            Ok(total)
+        })();
+
+        match result {
+            Ok(total) => ok_println("PCR_COMPLETE", &format!("{} amplicons", total)),
+            Err(e) => e_exit("PCR", &e, 1),
+        }
+    }
+
+    /// Pairs the primer hits of one template into products
+    fn products(seq_id: &str, seq: &[u8], forward: &Motif, reverse: &Motif, max_size: usize) -> Vec<MotifHit> {
+        let forward_hits = forward.find(seq_id, seq, true);
+        let reverse_hits = reverse.find(seq_id, seq, true);
+        let name = format!("{}/{}", forward.name, reverse.name);
+
+        // (upstream primer strand, downstream primer strand) for products on each template strand
+        let pairs = [(&forward_hits, &reverse_hits, '+'), (&reverse_hits, &forward_hits, '-')];
+        let mut products = Vec::new();
+        for (upstream_hits, downstream_hits, strand) in pairs {
+            for upstream in upstream_hits.iter().filter(|hit| hit.strand == '+') {
+                for downstream in downstream_hits.iter().filter(|hit| hit.strand == '-') {
+                    if downstream.start < upstream.start || downstream.end <= upstream.end
+                        || downstream.end - upstream.start > max_size {
+                        continue;
+                    }
+                    let amplicon = &seq[upstream.start..downstream.end];
+                    products.push(MotifHit {
+                        seq_id: seq_id.to_string(),
+                        start: upstream.start,
+                        end: downstream.end,
+                        strand,
+                        motif: name.clone(),
+                        matched: if strand == '+' { amplicon.to_vec() } else { reverse_complement(amplicon) },
+                        distance: upstream.distance + downstream.distance,
+                    });
+                }
+            }
+        }
+        products.sort_by_key(|product| (product.start, product.end));
+        products
+    }
+}
+
+/// Start codons accepted for ORFs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartCodons {
+    Atg,         // ATG only
+    Alternative, // Every start codon of the genetic code
+    Any,         // No start codon, ORFs run from stop to stop
+}
+
+/// ORF search options
+#[derive(Debug, Clone)]
+pub struct OrfOptions {
+    pub min_len: usize,       // Minimum ORF length in bases, stop codon included
+    pub code: GeneticCode,
+    pub starts: StartCodons,
+    pub nested: bool,         // Report every in-frame start, not only the longest ORF per stop codon
+}
+
+/// An open reading frame, in 0-based half-open forward-strand coordinates
+struct Orf {
+    start: usize,
+    end: usize,
+    strand: char,
+    frame: usize,         // 1 to 3, counted from the 5' end of the ORF strand
+    nucleotides: Vec<u8>, // ORF sequence on its strand, stop codon included
+    protein: Vec<u8>,     // Translation, without the stop
+}
+
+/// Find open reading frames in sequence files
+pub struct ExtractOrf;
+
+impl ExtractOrf {
+    /// Finds complete ORFs (start to stop codon) in the six frames of every record
+    ///
+    /// Writes `orfs.fna` (nucleotides), `orfs.faa` (proteins) and `orfs.gff3` (`ORF` features,
+    /// usable with `extract explain --type ORF`) to the output directory.
+    /// Records detected as proteins are skipped.
+    ///
+    /// # Arguments
+    /// * `paths` - Input sequence files (FASTA, FASTQ)
+    /// * `out_dir` - Output directory
+    /// * `options` - Minimum length, genetic code, start codons and nested-ORF policy
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn extract(paths: Vec<PathBuf>, out_dir: PathBuf, options: &OrfOptions, line_width: usize) {
+        let result = (|| -> Result<usize, String> {
+            fs::create_dir_all(&out_dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
+            let fasta = |name: &str| -> Result<fasta::Writer<File>, String> {
+                let path = out_dir.join(name);
+                let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+                let mut writer = fasta::Writer::new(file);
+                writer.set_linewrap(line_wrap(line_width));
+                Ok(writer)
+            };
+            let (mut fna, mut faa) = (fasta("orfs.fna")?, fasta("orfs.faa")?);
+            let mut gff = AnnotationWriter::new(&out_dir.join("orfs.gff3"), GffType::GFF3)
+                .map_err(|e| format!("Failed to create orfs.gff3: {}", e))?;
+
+            let mut total = 0;
+            for path in &paths {
+                let records = read_seq_records(path)?.collect::<Result<Vec<_>, String>>()?;
+                let orfs: Vec<Vec<Orf>> = records.par_iter()
+                    .map(|record| match Alphabet::detect(&record.seq) {
+                        Some((Alphabet::Protein, _)) => {
+                            e_println("ORF-SKIP", &format!("{} is a protein sequence", record.id));
+                            Vec::new()
+                        }
+                        _ => Self::find_orfs(&record.seq, options),
+                    })
+                    .collect();
+
+                let count: usize = orfs.iter().map(Vec::len).sum();
+                ok_println("ORF", &format!("{} ({} ORFs)", path.display(), count));
+                total += count;
+
+                for (record, orfs) in records.iter().zip(orfs) {
+                    for (n, orf) in orfs.iter().enumerate() {
+                        let id = format!("{}_ORF{}", record.id, n + 1);
+                        let desc = format!(
+                            "{}:{}-{}({}) frame={}{} length={}",
+                            record.id, orf.start, orf.end, orf.strand, orf.strand, orf.frame, orf.protein.len()
+                        );
+                        fna.write(&id, Some(&desc), &orf.nucleotides)
+                            .and_then(|_| faa.write(&id, Some(&desc), &orf.protein))
+                            .map_err(|e| format!("Write failed: {}", e))?;
+
+                        let mut gff_record = gff::Record::new();
+                        *gff_record.seqname_mut() = record.id.clone();
+                        *gff_record.source_mut() = "seq-here".to_string();
+                        *gff_record.feature_type_mut() = "ORF".to_string();
+                        *gff_record.start_mut() = orf.start as u64 + 1;
+                        *gff_record.end_mut() = orf.end as u64;
+                        *gff_record.score_mut() = ".".to_string();
+                        *gff_record.strand_mut() = orf.strand.to_string();
+                        *gff_record.phase_mut() = Phase::from(0);
+                        gff_record.attributes_mut().insert("ID".to_string(), id);
+                        gff_record.attributes_mut().insert("frame".to_string(), format!("{}{}", orf.strand, orf.frame));
+                        gff_record.attributes_mut().insert("transl_table".to_string(), options.code.id.to_string());
+                        gff.write(&gff_record).map_err(|e| format!("Write failed: {}", e))?;
+                    }
+                }
+            }
+            fna.flush()
+                .and_then(|_| faa.flush())
+                .and_then(|_| gff.flush())
+                .map_err(|e| format!("Write failed: {}", e))?;
+            Ok(total)
+        })();
+
+        match result {
+            Ok(total) => ok_println("ORF_COMPLETE", &format!("{} ORFs", total)),
+            Err(e) => e_exit("ORF", &e, 1),
+        }
+    }
+
+    /// Finds the ORFs of both strands, sorted by forward-strand position
+    fn find_orfs(seq: &[u8], options: &OrfOptions) -> Vec<Orf> {
+        let revcomp = reverse_complement(seq);
+        let mut orfs = Vec::new();
+        for (strand, strand_seq) in [('+', seq), ('-', revcomp.as_slice())] {
+            for frame in 0..3 {
+                for (start, end) in Self::scan_frame(strand_seq, frame, options) {
+                    let nucleotides = strand_seq[start..end].to_vec();
+                    let mut protein = options.code.translate(&nucleotides[..nucleotides.len() - 3]);
+                    // Alternative start codons are translated as methionine when they initiate
+                    if options.starts != StartCodons::Any {
+                        if let Some(first) = protein.first_mut() {
+                            *first = b'M';
+                        }
+                    }
+                    let (start, end) = if strand == '+' { (start, end) } else { (seq.len() - end, seq.len() - start) };
+                    orfs.push(Orf { start, end, strand, frame: frame + 1, nucleotides, protein });
+                }
+            }
+        }
+        orfs.sort_by_key(|orf| (orf.start, orf.end));
+        orfs
+    }
+
+    /// Scans one reading frame, returning the (start, end) of ORFs long enough
+    fn scan_frame(seq: &[u8], frame: usize, options: &OrfOptions) -> Vec<(usize, usize)> {
+        let mut orfs = Vec::new();
+        // In-frame starts since the last stop codon
+        let mut starts: Vec<usize> = Vec::new();
+        let mut region_start = frame; // First codon after the last stop codon
+
+        let mut i = frame;
+        while i + 3 <= seq.len() {
+            let codon = &seq[i..i + 3];
+            if options.code.translate_codon(codon) == b'*' {
+                let end = i + 3;
+                if options.starts == StartCodons::Any {
+                    starts.push(region_start);
+                }
+                let candidates = if options.nested { &starts[..] } else { &starts[..starts.len().min(1)] };
+                orfs.extend(candidates.iter()
+                    .filter(|&&start| end - start >= options.min_len && end - start > 3)
+                    .map(|&start| (start, end)));
+                starts.clear();
+                region_start = end;
+            } else if options.starts != StartCodons::Any
+                && options.code.is_start(codon, options.starts == StartCodons::Alternative) {
+                starts.push(i);
+            }
+            i += 3;
+        }
+        orfs
+    }
+}
+
+/// Writes motif hits as BED6 or GFF3
+struct HitWriter {
+    inner: io::BufWriter<File>,
+    format: MotifFormat,
+    feature_type: &'static str, // GFF3 feature type
+    id_prefix: &'static str,    // GFF3 IDs are `{seq_id}.{id_prefix}{n}`
+}
+
+impl HitWriter {
+    fn new(path: &Path, format: MotifFormat, feature_type: &'static str, id_prefix: &'static str) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut inner = io::BufWriter::new(file);
+        if format == MotifFormat::Gff3 {
+            writeln!(inner, "##gff-version 3").map_err(|e| format!("Write failed: {}", e))?;
+        }
+        Ok(Self { inner, format, feature_type, id_prefix })
+    }
+
+    /// Writes a hit, `n` numbers the hits of a record to build unique GFF3 IDs
+    ///
+    /// The distance of approximate hits goes to the BED score and to a GFF3 `distance` attribute.
+    fn write(&mut self, hit: &MotifHit, n: usize) -> Result<(), String> {
+        match self.format {
+            MotifFormat::Bed => writeln!(
+                self.inner,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                hit.seq_id, hit.start, hit.end, hit.motif, hit.distance, hit.strand
+            ),
+            MotifFormat::Gff3 => writeln!(
+                self.inner,
+                "{}\tseq-here\t{}\t{}\t{}\t.\t{}\t.\tID={}.{}{};Name={};sequence={}{}",
+                hit.seq_id, self.feature_type, hit.start + 1, hit.end, hit.strand,
+                hit.seq_id, self.id_prefix, n, hit.motif, String::from_utf8_lossy(&hit.matched),
+                if hit.distance > 0 { format!(";distance={}", hit.distance) } else { String::new() }
+            ),
+        }
+        .map_err(|e| format!("Write failed: {}", e))
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.inner.flush().map_err(|e| format!("Write failed: {}", e))
+    }
+}
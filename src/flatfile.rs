@@ -0,0 +1,441 @@
+//! Flat file
+//!
+//! Readers for the GenBank and EMBL flat file formats.
+//! Both formats keep the sequence together with its feature table in one record,
+//! so a parsed [`FlatRecord`] exposes the sequence, the features and their qualifiers.
+//!
+
+use crate::utils::FileType;
+use bio::bio_types::strand::Strand;
+use bio::io::fasta;
+use bio::io::gff::{self, Phase};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines};
+use std::path::Path;
+
+/// Flavour of the flat file to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatFormat {
+    GenBank, // NCBI GenBank flat file (.gb, .gbk)
+    Embl,    // EMBL/ENA flat file (.embl)
+}
+
+impl FlatFormat {
+    /// Name of the format, used as the `source` column when features are written as GFF
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlatFormat::GenBank => "GenBank",
+            FlatFormat::Embl => "EMBL",
+        }
+    }
+
+    /// Flavour of a flat file inferred from its extension, `None` for other file types
+    pub fn of_path(path: &Path) -> Option<FlatFormat> {
+        match FileType::infer_file_type(path) {
+            FileType::GenBank => Some(FlatFormat::GenBank),
+            FileType::Embl => Some(FlatFormat::Embl),
+            _ => None,
+        }
+    }
+}
+
+/// A single GenBank/EMBL entry
+#[derive(Debug, Clone, Default)]
+pub struct FlatRecord {
+    pub name: String,               // LOCUS name (GenBank) or ID line name (EMBL)
+    pub accession: Option<String>,  // First accession number
+    pub definition: Option<String>, // DEFINITION (GenBank) or DE (EMBL) text
+    pub features: Vec<Feature>,     // Feature table entries, in file order
+    pub seq: Vec<u8>,               // Upper-cased sequence
+}
+
+impl FlatRecord {
+    /// The sequence as a FASTA record named after the entry
+    pub fn to_fasta(&self) -> fasta::Record {
+        fasta::Record::with_attrs(&self.name, self.definition.as_deref(), &self.seq)
+    }
+
+    /// The feature table as GFF records on this entry's sequence
+    ///
+    /// Each feature gives one line per location range; see [`Feature::to_gff`].
+    pub fn gff_records(&self, format: FlatFormat) -> Vec<gff::Record> {
+        self.features
+            .iter()
+            .enumerate()
+            .flat_map(|(index, feature)| feature.to_gff(&self.name, index, format))
+            .collect()
+    }
+}
+
+/// An entry of the feature table
+#[derive(Debug, Clone)]
+pub struct Feature {
+    pub kind: String,                     // Feature key, e.g. "gene", "CDS"
+    pub location: Location,               // Parsed feature location
+    pub qualifiers: Vec<(String, String)>, // Qualifiers in file order, quotes removed
+}
+
+impl Feature {
+    /// Returns the first value of the given qualifier, if present
+    pub fn qualifier(&self, key: &str) -> Option<&str> {
+        self.qualifiers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Builds the annotation lines of a feature, one line per location range
+    ///
+    /// `source` features become `region`; CDS phases follow `/codon_start`.
+    /// Multi-part features share the ID `{seqname}_{kind}_{index + 1}`.
+    ///
+    /// # Arguments
+    /// * `seqname` - Name of the entry the feature belongs to
+    /// * `index` - Position of the feature in the feature table
+    /// * `format` - Flavour of the flat file, used as the `source` column
+    pub fn to_gff(&self, seqname: &str, index: usize, format: FlatFormat) -> Vec<gff::Record> {
+        let ranges = &self.location.ranges;
+        let reverse = self.location.strand == Strand::Reverse;
+
+        let phases = if self.kind == "CDS" {
+            let codon_start = self.qualifier("codon_start").and_then(|v| v.parse::<u64>().ok()).unwrap_or(1);
+            cds_phases(ranges, reverse, codon_start.saturating_sub(1))
+        } else {
+            vec![None; ranges.len()]
+        };
+
+        ranges.iter().zip(phases).map(|(&(start, end), phase)| {
+            let mut gff_record = gff::Record::new();
+            *gff_record.seqname_mut() = seqname.to_string();
+            *gff_record.source_mut() = format.name().to_string();
+            *gff_record.feature_type_mut() = match self.kind.as_str() {
+                "source" => "region".to_string(),
+                kind => kind.to_string(),
+            };
+            *gff_record.start_mut() = start;
+            *gff_record.end_mut() = end;
+            *gff_record.score_mut() = ".".to_string();
+            *gff_record.strand_mut() = if reverse { "-" } else { "+" }.to_string();
+            *gff_record.phase_mut() = Phase::from(phase);
+
+            // Multi-part features share an ID so they stay one feature
+            if ranges.len() > 1 {
+                gff_record.attributes_mut().insert("ID".to_string(), format!("{}_{}_{}", seqname, self.kind, index + 1));
+            }
+            for (key, value) in &self.qualifiers {
+                if key != "translation" {
+                    gff_record.attributes_mut().insert(key.clone(), value.clone());
+                }
+            }
+            gff_record
+        }).collect()
+    }
+}
+
+/// Location of a feature, as a list of 1-based inclusive ranges
+///
+/// `join(...)` and `order(...)` produce several ranges, sorted by start position.
+/// Remote references to other entries (`ACC:1..10`) are ignored.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub ranges: Vec<(u64, u64)>,
+    pub strand: Strand,
+}
+
+impl Location {
+    /// Parses an INSDC location string such as `complement(join(10..20,30..>40))`
+    ///
+    /// # Arguments
+    /// * `raw` - Location string as written in the feature table
+    ///
+    /// # Returns
+    /// * `Location` - The parsed ranges and strand
+    pub fn parse(raw: &str) -> Location {
+        let strand = if raw.contains("complement(") {
+            Strand::Reverse
+        } else {
+            Strand::Forward
+        };
+
+        let mut ranges: Vec<(u64, u64)> = raw
+            .split(',')
+            .filter(|part| !part.contains(':'))
+            .filter_map(|part| {
+                // Drop operators, parentheses and partial markers ('<', '>')
+                let cleaned: String = part
+                    .chars()
+                    .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '^')
+                    .collect();
+                let mut bounds = cleaned
+                    .split(['.', '^'])
+                    .filter(|s| !s.is_empty())
+                    .filter_map(|s| s.parse::<u64>().ok());
+                let start = bounds.next()?;
+                let end = bounds.next().unwrap_or(start);
+                Some((start.min(end), start.max(end)))
+            })
+            .collect();
+        ranges.sort_unstable();
+
+        Location { ranges, strand }
+    }
+
+    /// Leftmost position of the location (1-based)
+    pub fn start(&self) -> u64 {
+        self.ranges.first().map_or(0, |r| r.0)
+    }
+
+    /// Rightmost position of the location (1-based, inclusive)
+    pub fn end(&self) -> u64 {
+        self.ranges.iter().map(|r| r.1).max().unwrap_or(0)
+    }
+}
+
+/// Reads all records of a GenBank/EMBL file
+pub fn read_flat(path: &Path, format: FlatFormat) -> Result<Vec<FlatRecord>, String> {
+    let reader = FlatReader::from_file(path, format)
+        .map_err(|e| format!("Failed to read {} file: {} - {}", format.name(), path.display(), e))?;
+    reader.records()
+        .map(|r| r.map_err(|e| format!("Failed to parse {}: {}", format.name(), e)))
+        .collect()
+}
+
+/// GenBank/EMBL reader
+pub struct FlatReader<B: BufRead> {
+    lines: Lines<B>,
+    format: FlatFormat,
+}
+
+impl FlatReader<BufReader<File>> {
+    /// Opens a GenBank/EMBL file for reading
+    ///
+    /// # Arguments
+    /// * `path` - Path to the flat file
+    /// * `format` - Flavour of the flat file
+    pub fn from_file<P: AsRef<Path>>(path: P, format: FlatFormat) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?), format))
+    }
+}
+
+impl<B: BufRead> FlatReader<B> {
+    /// Creates a reader over any buffered input
+    pub fn new(reader: B, format: FlatFormat) -> Self {
+        Self {
+            lines: reader.lines(),
+            format,
+        }
+    }
+
+    /// Iterates over all records
+    pub fn records(self) -> FlatRecords<B> {
+        FlatRecords { reader: self }
+    }
+
+    /// Reads the next record, returning `None` at the end of the input
+    fn read_record(&mut self) -> Option<io::Result<FlatRecord>> {
+        let mut record = FlatRecord::default();
+        let mut feature_lines: Vec<String> = Vec::new();
+        let mut section = String::new();
+        let mut seen_content = false;
+
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(l) => l,
+                Err(e) => return Some(Err(e)),
+            };
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("//") {
+                return Some(Self::finish(record, &feature_lines));
+            }
+            seen_content = true;
+
+            match self.format {
+                FlatFormat::GenBank => {
+                    Self::parse_genbank_line(line, &mut section, &mut record, &mut feature_lines)
+                }
+                FlatFormat::Embl => Self::parse_embl_line(line, &mut record, &mut feature_lines),
+            }
+        }
+
+        // Tolerate a missing `//` terminator on the last record
+        if seen_content {
+            Some(Self::finish(record, &feature_lines))
+        } else {
+            None
+        }
+    }
+
+    /// Handles one line of a GenBank record
+    fn parse_genbank_line(
+        line: &str,
+        section: &mut String,
+        record: &mut FlatRecord,
+        feature_lines: &mut Vec<String>,
+    ) {
+        let continuation = line.starts_with(' ');
+        if !continuation {
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            *section = keyword.to_string();
+            let rest = rest.trim();
+            match keyword {
+                "LOCUS" => {
+                    record.name = rest.split_whitespace().next().unwrap_or_default().to_string()
+                }
+                "DEFINITION" => record.definition = Some(rest.to_string()),
+                "ACCESSION" => {
+                    record.accession = rest.split_whitespace().next().map(String::from)
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match section.as_str() {
+            "DEFINITION" => {
+                if let Some(def) = record.definition.as_mut() {
+                    def.push(' ');
+                    def.push_str(line.trim());
+                }
+            }
+            "FEATURES" => feature_lines.push(line.to_string()),
+            "ORIGIN" => record
+                .seq
+                .extend(line.bytes().filter(u8::is_ascii_alphabetic).map(|b| b.to_ascii_uppercase())),
+            _ => {}
+        }
+    }
+
+    /// Handles one line of an EMBL record
+    fn parse_embl_line(line: &str, record: &mut FlatRecord, feature_lines: &mut Vec<String>) {
+        let code = line.get(..2).unwrap_or(line);
+        let rest = line.get(2..).unwrap_or("");
+        match code {
+            "ID" => {
+                record.name = rest.trim().split(';').next().unwrap_or_default().trim().to_string()
+            }
+            "AC" if record.accession.is_none() => {
+                record.accession = rest.trim().split(';').next().map(|s| s.trim().to_string())
+            }
+            "DE" => match record.definition.as_mut() {
+                Some(def) => {
+                    def.push(' ');
+                    def.push_str(rest.trim());
+                }
+                None => record.definition = Some(rest.trim().to_string()),
+            },
+            // Feature lines share the GenBank column layout once the line code is blanked
+            "FT" => feature_lines.push(format!("  {}", rest)),
+            "  " => record
+                .seq
+                .extend(rest.bytes().filter(u8::is_ascii_alphabetic).map(|b| b.to_ascii_uppercase())),
+            _ => {}
+        }
+    }
+
+    /// Builds the feature table and completes the record
+    fn finish(mut record: FlatRecord, feature_lines: &[String]) -> io::Result<FlatRecord> {
+        record.features = parse_feature_table(feature_lines);
+        if record.name.is_empty() {
+            record.name = record.accession.clone().unwrap_or_default();
+        }
+        Ok(record)
+    }
+}
+
+/// Iterator over the records of a [`FlatReader`]
+pub struct FlatRecords<B: BufRead> {
+    reader: FlatReader<B>,
+}
+
+impl<B: BufRead> Iterator for FlatRecords<B> {
+    type Item = io::Result<FlatRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_record()
+    }
+}
+
+/// Parses feature table lines laid out as
+/// `     key             location` followed by
+/// `                     /qualifier="value"` continuation lines.
+fn parse_feature_table(lines: &[String]) -> Vec<Feature> {
+    // Location strings are parsed once all their continuation lines are collected
+    let mut raw: Vec<(Feature, String)> = Vec::new();
+
+    for line in lines {
+        let indent = line.len() - line.trim_start().len();
+        let content = line.trim();
+
+        if indent < 21 {
+            // New feature key line
+            let (kind, location) = content.split_once(char::is_whitespace).unwrap_or((content, ""));
+            let feature = Feature {
+                kind: kind.to_string(),
+                location: Location::parse(""),
+                qualifiers: Vec::new(),
+            };
+            raw.push((feature, location.trim().to_string()));
+            continue;
+        }
+
+        let Some((Feature { qualifiers, .. }, location)) = raw.last_mut() else {
+            continue;
+        };
+        if let Some(qualifier) = content.strip_prefix('/') {
+            let (key, value) = qualifier.split_once('=').unwrap_or((qualifier, ""));
+            qualifiers.push((key.to_string(), value.to_string()));
+        } else if let Some((key, value)) = qualifiers.last_mut() {
+            // Wrapped qualifier value; protein translations are wrapped without spaces
+            if key != "translation" {
+                value.push(' ');
+            }
+            value.push_str(content);
+        } else {
+            location.push_str(content);
+        }
+    }
+
+    raw.into_iter()
+        .map(|(mut feature, location)| {
+            feature.location = Location::parse(&location);
+            for (_, value) in feature.qualifiers.iter_mut() {
+                *value = unquote(value);
+            }
+            feature
+        })
+        .collect()
+}
+
+/// Removes the surrounding quotes of a qualifier value and unescapes doubled quotes
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .map(|v| v.strip_suffix('"').unwrap_or(v))
+        .unwrap_or(value);
+    value.replace("\"\"", "\"")
+}
+
+/// Computes the GFF phase of each CDS range (given in ascending order)
+///
+/// The phase is the number of bases to skip at the start of the range, in the direction
+/// of transcription, to reach the next codon.
+pub fn cds_phases(ranges: &[(u64, u64)], reverse: bool, first_phase: u64) -> Vec<Option<u8>> {
+    let mut phases = vec![None; ranges.len()];
+    let order: Vec<usize> = if reverse {
+        (0..ranges.len()).rev().collect()
+    } else {
+        (0..ranges.len()).collect()
+    };
+
+    let mut phase = first_phase % 3;
+    for i in order {
+        phases[i] = Some(phase as u8);
+        let len = ranges[i].1 - ranges[i].0 + 1;
+        phase = (3 - (len.saturating_sub(phase) % 3)) % 3;
+    }
+    phases
+}
@@ -1,13 +1,29 @@
+use crate::error::e_exit;
+use crate::flatfile::{read_flat, FlatFormat};
+use crate::process::TrimStats;
+use crate::sam::{flag, AlignmentReader, SamRecord, MAPQ_UNAVAILABLE};
 use crate::utils;
 use crate::utils::write_file;
 use bio::bio_types::strand::Strand;
 use bio::io::fasta;
 use bio::io::gff::GffType;
+use clap::ValueEnum;
 use colored::Colorize;
 use comfy_table::presets::NOTHING;
 use comfy_table::{ContentArrangement, Table};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the `info` commands write their tables
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputType {
+    File,
+    Println,
+    Csv,
+}
 
 /// Define the info fetch method
 ///
@@ -26,7 +42,7 @@ pub trait InfoOutput: InfoFetcher {
     fn by_file(paths: Vec<PathBuf>, args: Vec<String>) {
         let c = Self::info(paths, args);
         let path = PathBuf::from("info_fetch.txt");
-        write_file(path, &*c);
+        write_file(path, &c);
     }
     fn by_println(paths: Vec<PathBuf>, args: Vec<String>) {
         println!("{}", format_table(Self::info(paths.clone(), args)));
@@ -41,12 +57,16 @@ pub trait InfoOutput: InfoFetcher {
 
 pub struct InfoFa;
 impl InfoFetcher for InfoFa {
-    fn info(paths: Vec<PathBuf>, _args: Vec<String>) -> String {
+    fn info(paths: Vec<PathBuf>, args: Vec<String>) -> String {
+        // With "composition", a composition table replaces the default one
+        if args.iter().any(|a| a == "composition") {
+            return Self::composition(paths);
+        }
         let mut str_buf: Vec<String> = Vec::new();
 
         for (i, path) in paths.iter().enumerate() {
-            let reader = fasta::Reader::from_file(&path)
-                .expect(format!("{} reading file {}.", "Error".red().bold(), &path.display()).as_str());
+            let reader = fasta::Reader::from_file(path)
+                .unwrap_or_else(|_| panic!("{} reading file {}.", "Error".red().bold(), &path.display()));
             str_buf.push(format!("File: {:?} \n", path));
             str_buf.push(format!(
                 "{}\t{}\t{}\t{}\t{}\t\n",
@@ -56,16 +76,23 @@ impl InfoFetcher for InfoFa {
 
             for record in reader.records() {
                 let record =
-                    record.expect(format!("{} reading record.", "Error".red().bold()).as_str());
-                let s_type = utils::try_seq_type_seq(record.seq());
+                    record.unwrap_or_else(|_| panic!("{} reading record.", "Error".red().bold()));
+                let alphabet = utils::Alphabet::guess(record.seq());
+                let s_type = match alphabet {
+                    Some((alphabet, confidence)) if confidence >= utils::Alphabet::MIN_CONFIDENCE => {
+                        format!("{} ({:.2})", alphabet.name(), confidence)
+                    }
+                    Some((alphabet, confidence)) => format!("Undecided ({} {:.2})", alphabet.name(), confidence),
+                    None => "Unknown".to_string(),
+                };
                 str_buf.push(format!(
                     "{}\t{}\t{}\t{}\t{:.2}\t\n",
                     record.id(),
                     s_type,
                     record.desc().unwrap_or("None"),
                     record.seq().len(),
-                    match s_type.as_str() {
-                        "DNA" => bio::seq_analysis::gc::gc_content(&*record.seq()),
+                    match alphabet {
+                        Some((utils::Alphabet::Dna | utils::Alphabet::Rna, _)) => bio::seq_analysis::gc::gc_content(record.seq()),
                         _ => 0.0,
                     }
                 ));
@@ -90,16 +117,297 @@ impl InfoFetcher for InfoFa {
 
 impl InfoOutput for InfoFa {}
 
+impl InfoFa {
+    /// Per-record and per-file composition statistics, see [`Composition`]
+    fn composition(paths: Vec<PathBuf>) -> String {
+        let mut str_buf: Vec<String> = Vec::new();
+
+        for (i, path) in paths.iter().enumerate() {
+            let reader = fasta::Reader::from_file(path)
+                .unwrap_or_else(|_| panic!("{} reading file {}.", "Error".red().bold(), &path.display()));
+            str_buf.push(format!("File: {:?} \n", path));
+            str_buf.push(format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t\n",
+                "ID", "Seq Type", "Length", "Counts", "GC content", "GC skew", "CpG o/e",
+                "Soft-masked", "N runs", "Entropy", "Mol. weight", "pI"
+            ));
+            let mut total = Composition::default();
+            let mut alphabets = Vec::new();
+
+            for record in reader.records() {
+                let record =
+                    record.unwrap_or_else(|_| panic!("{} reading record.", "Error".red().bold()));
+                let mut composition = Composition::default();
+                composition.add(record.seq());
+                let alphabet = utils::Alphabet::detect(record.seq()).map(|(alphabet, _)| alphabet);
+                str_buf.push(composition.row(record.id(), alphabet, alphabet.map_or("Unknown", |a| a.name())));
+
+                total.merge(&composition);
+                if !alphabets.contains(&alphabet) {
+                    alphabets.push(alphabet);
+                }
+            }
+
+            // Protein-only values are kept for the total when every record is a protein
+            let (alphabet, name) = match alphabets.as_slice() {
+                [alphabet] => (*alphabet, alphabet.map_or("Unknown", |a| a.name())),
+                [] => (None, "-"),
+                _ => (None, "Mixed"),
+            };
+            str_buf.push(total.row("Total", alphabet, name));
+            str_buf.insert(
+                i,
+                format!(
+                    "File'{}' Total length/count : {}/{} \n",
+                    path.display(),
+                    total.len(),
+                    total.records
+                ),
+            );
+        }
+        str_buf.push("\n".to_string());
+        str_buf.into_iter().collect::<String>()
+    }
+}
+
+/// Residue composition of a sequence or of a set of sequences
+///
+/// Counts are case-insensitive, lower-case residues are additionally counted as soft-masked.
+/// Gaps (`-`, `.`) are not residues and are skipped. Sequences can be added in chunks,
+/// N runs and CpG dinucleotides spanning two chunks of the same sequence are counted once.
+#[derive(Debug, Clone)]
+pub struct Composition {
+    counts: [usize; 256],   // Upper-cased residue counts
+    soft_masked: usize,     // Lower-case residues
+    n_runs: usize,
+    longest_n_run: usize,
+    cpg: usize,
+    records: usize,
+    previous: Option<u8>,   // Last residue added, upper-cased
+    n_run: usize,           // Length of the current N run
+}
+
+impl Default for Composition {
+    fn default() -> Self {
+        Self {
+            counts: [0; 256],
+            soft_masked: 0,
+            n_runs: 0,
+            longest_n_run: 0,
+            cpg: 0,
+            records: 0,
+            previous: None,
+            n_run: 0,
+        }
+    }
+}
+
+impl Composition {
+    /// Average residue masses (Da) of the amino acids, peptide bonds included
+    const RESIDUE_MASSES: [(u8, f64); 25] = [
+        (b'A', 71.0788), (b'R', 156.1875), (b'N', 114.1038), (b'D', 115.0886), (b'C', 103.1388),
+        (b'E', 129.1155), (b'Q', 128.1307), (b'G', 57.0519), (b'H', 137.1411), (b'I', 113.1594),
+        (b'L', 113.1594), (b'K', 128.1741), (b'M', 131.1926), (b'F', 147.1766), (b'P', 97.1167),
+        (b'S', 87.0782), (b'T', 101.1051), (b'W', 186.2132), (b'Y', 163.1760), (b'V', 99.1326),
+        (b'U', 150.0388), (b'O', 237.3018), (b'B', 114.5962), (b'Z', 128.6231), (b'J', 113.1594),
+    ];
+    /// Average residue mass used for `X` and other unknown residues
+    const UNKNOWN_RESIDUE_MASS: f64 = 110.0;
+    const WATER_MASS: f64 = 18.01524;
+    /// pKa of the N-terminus, the C-terminus and the charged side chains (EMBOSS values)
+    const PKA_N_TERM: f64 = 8.6;
+    const PKA_C_TERM: f64 = 3.6;
+    const PKA_POSITIVE: [(u8, f64); 3] = [(b'K', 10.8), (b'R', 12.5), (b'H', 6.5)];
+    const PKA_NEGATIVE: [(u8, f64); 4] = [(b'D', 3.9), (b'E', 4.1), (b'C', 8.5), (b'Y', 10.1)];
+
+    /// Adds a sequence to the composition, as a new record
+    pub fn add(&mut self, seq: &[u8]) {
+        self.records += 1;
+        self.previous = None;
+        self.n_run = 0;
+        self.add_chunk(seq);
+    }
+
+    /// Adds the next chunk of the current record
+    pub fn add_chunk(&mut self, seq: &[u8]) {
+        for &c in seq {
+            if c == b'-' || c == b'.' {
+                continue;
+            }
+            if c.is_ascii_lowercase() {
+                self.soft_masked += 1;
+            }
+            let c = c.to_ascii_uppercase();
+            self.counts[c as usize] += 1;
+
+            if c == b'N' {
+                self.n_run += 1;
+                if self.n_run == 1 {
+                    self.n_runs += 1;
+                }
+                self.longest_n_run = self.longest_n_run.max(self.n_run);
+            } else {
+                self.n_run = 0;
+            }
+            if c == b'G' && self.previous == Some(b'C') {
+                self.cpg += 1;
+            }
+            self.previous = Some(c);
+        }
+    }
+
+    /// Adds the counts of another composition, records are kept apart
+    pub fn merge(&mut self, other: &Composition) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+        self.soft_masked += other.soft_masked;
+        self.n_runs += other.n_runs;
+        self.longest_n_run = self.longest_n_run.max(other.longest_n_run);
+        self.cpg += other.cpg;
+        self.records += other.records;
+    }
+
+    /// Number of residues, gaps excluded
+    pub fn len(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Count of a residue, of any case
+    pub fn count(&self, residue: u8) -> usize {
+        self.counts[residue.to_ascii_uppercase() as usize]
+    }
+
+    fn ratio(numerator: usize, denominator: usize) -> Option<f64> {
+        (denominator > 0).then(|| numerator as f64 / denominator as f64)
+    }
+
+    /// G+C share of the unambiguous nucleotides (A, C, G, T, U)
+    pub fn gc_content(&self) -> Option<f64> {
+        let gc = self.count(b'G') + self.count(b'C');
+        Self::ratio(gc, gc + self.count(b'A') + self.count(b'T') + self.count(b'U'))
+    }
+
+    /// GC skew, (G - C) / (G + C)
+    pub fn gc_skew(&self) -> Option<f64> {
+        let (g, c) = (self.count(b'G') as f64, self.count(b'C') as f64);
+        (g + c > 0.0).then(|| (g - c) / (g + c))
+    }
+
+    /// Observed/expected CpG ratio, CpG * length / (C * G) over the unambiguous nucleotides
+    pub fn cpg_observed_expected(&self) -> Option<f64> {
+        let (c, g) = (self.count(b'C'), self.count(b'G'));
+        let len = c + g + self.count(b'A') + self.count(b'T') + self.count(b'U');
+        Self::ratio(self.cpg * len, c * g)
+    }
+
+    /// Share of soft-masked (lower-case) residues
+    pub fn soft_masked_fraction(&self) -> Option<f64> {
+        Self::ratio(self.soft_masked, self.len())
+    }
+
+    /// Share of `N` residues
+    pub fn n_fraction(&self) -> Option<f64> {
+        Self::ratio(self.count(b'N'), self.len())
+    }
+
+    /// Shannon entropy of the residue distribution, in bits
+    pub fn entropy(&self) -> f64 {
+        let len = self.len() as f64;
+        self.counts.iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    /// Average molecular weight (Da) of a protein, stops excluded
+    pub fn molecular_weight(&self) -> f64 {
+        let residues: f64 = (0..=255u8)
+            .filter(|c| c.is_ascii_uppercase())
+            .map(|c| {
+                let mass = Self::RESIDUE_MASSES.iter()
+                    .find(|(residue, _)| *residue == c)
+                    .map_or(Self::UNKNOWN_RESIDUE_MASS, |(_, mass)| *mass);
+                self.counts[c as usize] as f64 * mass
+            })
+            .sum();
+        residues + Self::WATER_MASS * self.records.max(1) as f64
+    }
+
+    /// Isoelectric point of a protein, the pH where its net charge is zero
+    pub fn isoelectric_point(&self) -> f64 {
+        let records = self.records.max(1) as f64;
+        let charge = |ph: f64| {
+            let positive = |pka: f64, n: f64| n / (1.0 + 10f64.powf(ph - pka));
+            let negative = |pka: f64, n: f64| n / (1.0 + 10f64.powf(pka - ph));
+            positive(Self::PKA_N_TERM, records) - negative(Self::PKA_C_TERM, records)
+                + Self::PKA_POSITIVE.iter().map(|&(c, pka)| positive(pka, self.count(c) as f64)).sum::<f64>()
+                - Self::PKA_NEGATIVE.iter().map(|&(c, pka)| negative(pka, self.count(c) as f64)).sum::<f64>()
+        };
+
+        // The net charge decreases with the pH, so bisect over the pH scale
+        let (mut low, mut high) = (0.0, 14.0);
+        while high - low > 0.001 {
+            let mid = (low + high) / 2.0;
+            if charge(mid) > 0.0 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.0
+    }
+
+    /// Residue counts as `A:10 C:4 ...`, in alphabetical order
+    fn counts_str(&self) -> String {
+        self.counts.iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(c, count)| format!("{}:{}", c as u8 as char, count))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Table row of the composition, values that do not apply to the alphabet are `-`
+    fn row(&self, id: &str, alphabet: Option<utils::Alphabet>, type_name: &str) -> String {
+        let value = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.2}", v));
+        let nucleotide = matches!(alphabet, Some(utils::Alphabet::Dna | utils::Alphabet::Rna));
+        let protein = alphabet == Some(utils::Alphabet::Protein) && !self.is_empty();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.3}\t{}\t{}\t\n",
+            id,
+            type_name,
+            self.len(),
+            self.counts_str(),
+            value(self.gc_content().filter(|_| nucleotide)),
+            value(self.gc_skew().filter(|_| nucleotide)),
+            value(self.cpg_observed_expected().filter(|_| nucleotide)),
+            value(self.soft_masked_fraction()),
+            if nucleotide { format!("{} (max {})", self.n_runs, self.longest_n_run) } else { "-".to_string() },
+            self.entropy(),
+            if protein { format!("{:.1}", self.molecular_weight()) } else { "-".to_string() },
+            if protein { format!("{:.2}", self.isoelectric_point()) } else { "-".to_string() },
+        )
+    }
+}
+
 
 pub struct InfoFq;
 
 impl InfoFetcher for InfoFq {
-    fn info(paths: Vec<PathBuf>, _args: Vec<String>) -> String {
+    fn info(paths: Vec<PathBuf>, args: Vec<String>) -> String {
         let mut str_buf: Vec<String> = Vec::new();
 
         for (i, path) in paths.iter().enumerate() {
-            let reader = bio::io::fastq::Reader::from_file(&path)
-                .expect(format!("{} reading file {}.", "Error".red().bold(), &path.display()).as_str());
+            let reader = bio::io::fastq::Reader::from_file(path)
+                .unwrap_or_else(|_| panic!("{} reading file {}.", "Error".red().bold(), &path.display()));
             str_buf.push(format!("File: {:?} \n", path));
             str_buf.push(format!(
                 "{}\t{}\t{}\t{}\t\n",
@@ -109,7 +417,7 @@ impl InfoFetcher for InfoFq {
 
             for record in reader.records() {
                 let record =
-                    record.expect(format!("{} reading record.", "Error".red().bold()).as_str());
+                    record.unwrap_or_else(|_| panic!("{} reading record.", "Error".red().bold()));
                 str_buf.push(format!(
                     "{}\t{}\t{}\t{}\t\n",
                     record.id(),
@@ -121,15 +429,24 @@ impl InfoFetcher for InfoFq {
                 total_len += record.seq().len();
                 count += 1;
             }
-            str_buf.insert(
-                i,
-                format!(
-                    "File'{}' Total length/count : {}/{} \n",
-                    path.display(),
-                    total_len,
-                    count
-                ),
-            );
+            str_buf.insert(i, Self::summary(path, total_len, count));
+        }
+
+        // With "paired", consecutive files are R1/R2 mates and their read names are checked
+        if args.iter().any(|a| a == "paired") {
+            for (i, pair) in paths.chunks(2).enumerate() {
+                let line = match pair {
+                    [r1, r2] => format!(
+                        "Pair'{}' + '{}' : {} \n",
+                        r1.display(),
+                        r2.display(),
+                        Self::check_pairs(r1, r2)
+                    ),
+                    [single] => format!("Pair'{}' : no mate file \n", single.display()),
+                    _ => continue,
+                };
+                str_buf.insert(paths.len() + i, line);
+            }
         }
 
         str_buf.push("\n".to_string());
@@ -138,6 +455,40 @@ impl InfoFetcher for InfoFq {
 }
 impl InfoOutput for InfoFq {}
 
+impl InfoFq {
+    /// Total length/count line of a FASTQ file, shared with the `process trim` report
+    pub fn summary(path: &Path, total_len: usize, count: usize) -> String {
+        format!("File'{}' Total length/count : {}/{} \n", path.display(), total_len, count)
+    }
+
+    /// Counts the mate pairs of two files and the pairs whose read names disagree
+    fn check_pairs(r1: &Path, r2: &Path) -> String {
+        let pairs = match utils::read_mate_pairs(r1, r2) {
+            Ok(pairs) => pairs,
+            Err(e) => return e,
+        };
+
+        let (mut count, mut mismatched) = (0, 0);
+        let mut first_mismatch = None;
+        for pair in pairs {
+            let (a, b) = match pair {
+                Ok(pair) => pair,
+                Err(e) => return format!("{} after {} pairs", e, count),
+            };
+            count += 1;
+            if !utils::is_mate_pair(&a, &b) {
+                mismatched += 1;
+                first_mismatch.get_or_insert(format!("pair {}: {} / {}", count, a.id, b.id));
+            }
+        }
+
+        match first_mismatch {
+            None => format!("{} pairs, names in sync", count),
+            Some(first) => format!("{} pairs, {} with mismatched names (first at {})", count, mismatched, first),
+        }
+    }
+}
+
 
 pub struct InfoGff;
 
@@ -151,8 +502,8 @@ impl InfoFetcher for InfoGff {
         };
 
         for (i, path) in paths.iter().enumerate() {
-            let mut reader = bio::io::gff::Reader::from_file(&path, gff_type)
-                .expect(format!("{} reading file {}.", "Error".red().bold(), &path.display()).as_str());
+            let mut reader = bio::io::gff::Reader::from_file(path, gff_type)
+                .unwrap_or_else(|_| panic!("{} reading file {}.", "Error".red().bold(), &path.display()));
             str_buf.push(format!("File: {:?} \n", path));
 
             let mut count = 0;
@@ -203,6 +554,603 @@ impl InfoFetcher for InfoGff {
 impl InfoOutput for InfoGff {}
 
 
+pub struct InfoBed;
+
+impl InfoFetcher for InfoBed {
+    fn info(paths: Vec<PathBuf>, _args: Vec<String>) -> String {
+        let mut str_buf: Vec<String> = Vec::new();
+
+        for (i, path) in paths.iter().enumerate() {
+            let records = utils::read_bed(path)
+                .unwrap_or_else(|e| panic!("{} reading file {}: {}", "Error".red().bold(), path.display(), e));
+            str_buf.push(format!("File: {:?} \n", path));
+            str_buf.push(format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t\n",
+                "Chrom", "Intervals", "Total length", "Covered", "Min length", "Max length", "Mean length"
+            ));
+
+            // Intervals per chromosome, chromosomes in order of first appearance
+            let mut chroms: Vec<(String, Vec<(u64, u64)>)> = Vec::new();
+            let mut index: HashMap<String, usize> = HashMap::new();
+            for record in &records {
+                let i = match index.get(record.chrom()) {
+                    Some(&i) => i,
+                    None => {
+                        index.insert(record.chrom().to_string(), chroms.len());
+                        chroms.push((record.chrom().to_string(), Vec::new()));
+                        chroms.len() - 1
+                    }
+                };
+                chroms[i].1.push((record.start(), record.end()));
+            }
+
+            let (mut total_len, mut total_covered) = (0, 0);
+            for (chrom, intervals) in &mut chroms {
+                let lengths: Vec<u64> = intervals.iter().map(|(start, end)| end - start).collect();
+                let length: u64 = lengths.iter().sum();
+                let covered = Self::covered(intervals);
+                str_buf.push(format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{:.1}\t\n",
+                    chrom,
+                    intervals.len(),
+                    length,
+                    covered,
+                    lengths.iter().min().unwrap_or(&0),
+                    lengths.iter().max().unwrap_or(&0),
+                    length as f64 / intervals.len() as f64
+                ));
+                total_len += length;
+                total_covered += covered;
+            }
+
+            str_buf.insert(
+                i,
+                format!(
+                    "File'{}' Total count/length/covered : {}/{}/{} \n",
+                    path.display(),
+                    records.len(),
+                    total_len,
+                    total_covered
+                ),
+            );
+        }
+        str_buf.push("\n".to_string());
+        str_buf.into_iter().collect::<String>()
+    }
+}
+
+impl InfoOutput for InfoBed {}
+
+/// Report of `process trim`, in the layout of `info fq`
+pub struct InfoTrim;
+
+impl InfoTrim {
+    /// Outputs the counts of each trimmed file like the `info` commands do
+    ///
+    /// # Arguments
+    /// * `paths` - Input files of the trimming
+    /// * `stats` - Counts of each input file, in the order of `paths`
+    /// * `output_type` - Terminal table, `info_fetch.txt` or `info_fetch.csv`
+    pub fn report(paths: &[PathBuf], stats: &[TrimStats], output_type: OutputType) {
+        let c = Self::table(paths, stats);
+        match output_type {
+            OutputType::File => write_file("info_fetch.txt", &c),
+            OutputType::Println => println!("{}", format_table(c)),
+            OutputType::Csv => write_file("info_fetch.csv", &c),
+        }
+    }
+
+    fn table(paths: &[PathBuf], stats: &[TrimStats]) -> String {
+        let mut str_buf: Vec<String> = Vec::new();
+
+        for (i, (path, s)) in paths.iter().zip(stats).enumerate() {
+            str_buf.push(format!("File: {:?} \n", path));
+            str_buf.push(format!("{}\t{}\t{}\t\n", "Step", "Reads", "Bases"));
+            let rows = [
+                ("Input", s.reads_in.to_string(), s.bases_in.to_string()),
+                ("Head/tail clipping", "-".to_string(), s.clipped.to_string()),
+                ("Adapters", s.adapter_reads.to_string(), s.adapter_bases.to_string()),
+                ("Poly-G/poly-A tails", s.poly_reads.to_string(), s.poly_bases.to_string()),
+                ("Low quality", "-".to_string(), s.quality_bases.to_string()),
+                ("Too short (removed)", s.too_short.to_string(), "-".to_string()),
+                ("Output", s.reads_out.to_string(), s.bases_out.to_string()),
+            ];
+            for (step, reads, bases) in rows {
+                str_buf.push(format!("{}\t{}\t{}\t\n", step, reads, bases));
+            }
+            str_buf.insert(i, InfoFq::summary(path, s.bases_in, s.reads_in));
+        }
+
+        str_buf.push("\n".to_string());
+        str_buf.into_iter().collect::<String>()
+    }
+}
+
+pub struct InfoGb;
+
+impl InfoFetcher for InfoGb {
+    fn info(paths: Vec<PathBuf>, _args: Vec<String>) -> String {
+        let mut str_buf: Vec<String> = Vec::new();
+
+        for (i, path) in paths.iter().enumerate() {
+            let format = FlatFormat::of_path(path).unwrap_or(FlatFormat::GenBank);
+            let records = read_flat(path, format)
+                .unwrap_or_else(|e| panic!("{} reading file {}: {}", "Error".red().bold(), path.display(), e));
+            str_buf.push(format!("File: {:?} \n", path));
+            str_buf.push(format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t\n",
+                "Name", "Accession", "Length", "GC content", "Features", "Genes", "CDS", "Definition"
+            ));
+
+            let (mut total_len, mut total_features) = (0, 0);
+            for record in &records {
+                let count = |kind: &str| record.features.iter().filter(|f| f.kind == kind).count();
+                str_buf.push(format!(
+                    "{}\t{}\t{}\t{:.2}\t{}\t{}\t{}\t{}\t\n",
+                    record.name,
+                    record.accession.as_deref().unwrap_or("None"),
+                    record.seq.len(),
+                    bio::seq_analysis::gc::gc_content(&record.seq),
+                    record.features.len(),
+                    count("gene"),
+                    count("CDS"),
+                    record.definition.as_deref().unwrap_or("None")
+                ));
+                total_len += record.seq.len();
+                total_features += record.features.len();
+            }
+
+            str_buf.insert(
+                i,
+                format!(
+                    "File'{}' ({}) Total count/length/features : {}/{}/{} \n",
+                    path.display(),
+                    format.name(),
+                    records.len(),
+                    total_len,
+                    total_features
+                ),
+            );
+        }
+        str_buf.push("\n".to_string());
+        str_buf.into_iter().collect::<String>()
+    }
+}
+
+impl InfoOutput for InfoGb {}
+
+pub struct InfoSam;
+
+impl InfoFetcher for InfoSam {
+    fn info(paths: Vec<PathBuf>, _args: Vec<String>) -> String {
+        let mut str_buf: Vec<String> = Vec::new();
+
+        for (i, path) in paths.iter().enumerate() {
+            let stats = Self::flagstat(path)
+                .unwrap_or_else(|e| panic!("{} reading file {}: {}", "Error".red().bold(), path.display(), e));
+            str_buf.push(format!("File: {:?} \n", path));
+            str_buf.push(format!("{}\t{}\t{}\t\n", "Category", "Reads", "Percent"));
+            for (category, count, of) in stats.rows() {
+                let percent = if of > 0 { format!("{:.2}%", count as f64 * 100.0 / of as f64) } else { "-".to_string() };
+                str_buf.push(format!("{}\t{}\t{}\t\n", category, count, percent));
+            }
+
+            str_buf.insert(
+                i,
+                format!(
+                    "File'{}' Total/mapped/unmapped : {}/{}/{} \n",
+                    path.display(),
+                    stats.total,
+                    stats.mapped,
+                    stats.total - stats.mapped
+                ),
+            );
+        }
+        str_buf.push("\n".to_string());
+        str_buf.into_iter().collect::<String>()
+    }
+}
+
+impl InfoOutput for InfoSam {}
+
+impl InfoSam {
+    /// Counts the alignments of a SAM/BAM file by flag and MAPQ
+    fn flagstat(path: &Path) -> Result<FlagStats, String> {
+        let reader = AlignmentReader::from_file(path)
+            .map_err(|e| format!("Failed to read alignment file: {}", e))?;
+        let mut stats = FlagStats::default();
+        for record in reader.records() {
+            stats.add(&record?);
+        }
+        Ok(stats)
+    }
+}
+
+/// Alignment counts in the style of `samtools flagstat`
+///
+/// All lines are counted in `total` and `mapped`; pairing counts and the MAPQ
+/// distribution only use primary lines, so each read is counted once.
+#[derive(Debug, Clone, Default)]
+pub struct FlagStats {
+    pub total: usize,
+    pub primary: usize,
+    pub secondary: usize,
+    pub supplementary: usize,
+    pub duplicates: usize,
+    pub qc_failed: usize,
+    pub mapped: usize,
+    pub primary_mapped: usize,
+    pub paired: usize,
+    pub read1: usize,
+    pub read2: usize,
+    pub proper_pairs: usize,
+    pub both_mapped: usize,        // Paired reads mapped with their mate mapped
+    pub singletons: usize,         // Paired reads mapped with their mate unmapped
+    pub mate_other_ref: usize,     // Both mapped, mate on another reference
+    pub mapq: BTreeMap<u8, usize>, // Primary mapped reads by MAPQ
+}
+
+impl FlagStats {
+    /// MAPQ bins reported by [`FlagStats::rows`], inclusive
+    const MAPQ_BINS: [(u8, u8); 7] = [(0, 0), (1, 9), (10, 19), (20, 29), (30, 39), (40, 59), (60, 254)];
+
+    /// Counts one alignment line
+    pub fn add(&mut self, record: &SamRecord) {
+        let mapped = !record.is_unmapped();
+        self.total += 1;
+        self.mapped += mapped as usize;
+        self.duplicates += record.has(flag::DUPLICATE) as usize;
+        self.qc_failed += record.has(flag::QC_FAIL) as usize;
+        if record.has(flag::SECONDARY) {
+            self.secondary += 1;
+            return;
+        }
+        if record.has(flag::SUPPLEMENTARY) {
+            self.supplementary += 1;
+            return;
+        }
+
+        self.primary += 1;
+        if mapped {
+            self.primary_mapped += 1;
+            *self.mapq.entry(record.mapq).or_insert(0) += 1;
+        }
+        if !record.has(flag::PAIRED) {
+            return;
+        }
+        self.paired += 1;
+        self.read1 += record.has(flag::READ1) as usize;
+        self.read2 += record.has(flag::READ2) as usize;
+        self.proper_pairs += (mapped && record.has(flag::PROPER_PAIR)) as usize;
+        if mapped && record.has(flag::MATE_UNMAPPED) {
+            self.singletons += 1;
+        } else if mapped {
+            self.both_mapped += 1;
+            self.mate_other_ref += (record.rnext.is_some() && record.rnext != record.rname) as usize;
+        }
+    }
+
+    /// Report rows: category, count, and the count it is a percentage of (0 for none)
+    pub fn rows(&self) -> Vec<(String, usize, usize)> {
+        let mut rows: Vec<(String, usize, usize)> = vec![
+            ("Total".to_string(), self.total, 0),
+            ("Primary".to_string(), self.primary, self.total),
+            ("Secondary".to_string(), self.secondary, self.total),
+            ("Supplementary".to_string(), self.supplementary, self.total),
+            ("Duplicates".to_string(), self.duplicates, self.total),
+            ("QC failed".to_string(), self.qc_failed, self.total),
+            ("Mapped".to_string(), self.mapped, self.total),
+            ("Unmapped".to_string(), self.total - self.mapped, self.total),
+            ("Primary mapped".to_string(), self.primary_mapped, self.primary),
+            ("Paired".to_string(), self.paired, self.primary),
+            ("Read 1".to_string(), self.read1, self.paired),
+            ("Read 2".to_string(), self.read2, self.paired),
+            ("Properly paired".to_string(), self.proper_pairs, self.paired),
+            ("With mate mapped".to_string(), self.both_mapped, self.paired),
+            ("Singletons".to_string(), self.singletons, self.paired),
+            ("Mate on other reference".to_string(), self.mate_other_ref, self.both_mapped),
+        ];
+        for (low, high) in Self::MAPQ_BINS {
+            let count = self.mapq.range(low..=high).map(|(_, n)| n).sum();
+            let label = if low == high { format!("MAPQ {}", low) } else { format!("MAPQ {}-{}", low, high) };
+            rows.push((label, count, self.primary_mapped));
+        }
+        rows.push(("MAPQ unavailable".to_string(), self.mapq.get(&MAPQ_UNAVAILABLE).copied().unwrap_or(0), self.primary_mapped));
+        rows
+    }
+}
+
+impl InfoBed {
+    /// Bases covered by at least one interval, overlaps counted once
+    fn covered(intervals: &mut [(u64, u64)]) -> u64 {
+        intervals.sort_unstable();
+        let (mut covered, mut reached) = (0, 0);
+        for &(start, end) in intervals.iter() {
+            let start = start.max(reached);
+            if end > start {
+                covered += end - start;
+                reached = end;
+            }
+        }
+        covered
+    }
+}
+
+
+/// Statistic reported per window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowStat {
+    Gc,
+    GcSkew,
+    NDensity,
+    SoftMasked,
+}
+
+impl WindowStat {
+    pub const ALL: [WindowStat; 4] = [WindowStat::Gc, WindowStat::GcSkew, WindowStat::NDensity, WindowStat::SoftMasked];
+
+    /// Column and track name of the statistic
+    pub fn name(&self) -> &'static str {
+        match self {
+            WindowStat::Gc => "gc",
+            WindowStat::GcSkew => "gc_skew",
+            WindowStat::NDensity => "n_density",
+            WindowStat::SoftMasked => "soft_masked",
+        }
+    }
+
+    fn value(&self, composition: &Composition) -> Option<f64> {
+        match self {
+            WindowStat::Gc => composition.gc_content(),
+            WindowStat::GcSkew => composition.gc_skew(),
+            WindowStat::NDensity => composition.n_fraction(),
+            WindowStat::SoftMasked => composition.soft_masked_fraction(),
+        }
+    }
+}
+
+/// Sliding-window statistics of FASTA/FASTQ records
+///
+/// Windows are `size` bases long and start every `step` bases; the last window of a record
+/// is cut at its end. Values come from [`Composition`], so GC ignores `N` and ambiguity codes.
+pub struct InfoWindow;
+
+impl InfoWindow {
+    /// Writes window statistics as bedGraph (one statistic) or TSV (all statistics)
+    ///
+    /// # Arguments
+    /// * `paths` - Input sequence files
+    /// * `size` - Window size in bases
+    /// * `step` - Distance between window starts
+    /// * `stat` - Statistic of the bedGraph track, all statistics are written as TSV if `None`
+    /// * `output` - Output file, standard output if `None`
+    pub fn report(paths: Vec<PathBuf>, size: usize, step: usize, stat: Option<WindowStat>, output: Option<PathBuf>) {
+        if size == 0 || step == 0 {
+            e_exit("WINDOW", "Window size and step must be greater than 0", 1);
+        }
+
+        let result = (|| -> Result<(), String> {
+            let writer: Box<dyn Write> = match &output {
+                Some(path) => Box::new(File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            let mut writer = BufWriter::new(writer);
+            match stat {
+                Some(stat) => writeln!(writer, "track type=bedGraph name=\"{}\"", stat.name()),
+                None => writeln!(
+                    writer,
+                    "chrom\tstart\tend\t{}",
+                    WindowStat::ALL.iter().map(|s| s.name()).collect::<Vec<_>>().join("\t")
+                ),
+            }
+            .map_err(|e| format!("Write failed: {}", e))?;
+
+            for path in &paths {
+                for record in utils::read_seq_records(path)? {
+                    let record = record?;
+                    for (start, end) in Self::windows(record.seq.len(), size, step) {
+                        let mut composition = Composition::default();
+                        composition.add(&record.seq[start..end]);
+                        let line = match stat {
+                            // bedGraph has no missing values, windows without one are left out
+                            Some(stat) => match stat.value(&composition) {
+                                Some(value) => format!("{}\t{}\t{}\t{:.4}", record.id, start, end, value),
+                                None => continue,
+                            },
+                            None => {
+                                let values: Vec<String> = WindowStat::ALL.iter()
+                                    .map(|s| s.value(&composition).map_or("NA".to_string(), |v| format!("{:.4}", v)))
+                                    .collect();
+                                format!("{}\t{}\t{}\t{}", record.id, start, end, values.join("\t"))
+                            }
+                        };
+                        writeln!(writer, "{}", line).map_err(|e| format!("Write failed: {}", e))?;
+                    }
+                }
+            }
+            writer.flush().map_err(|e| format!("Write failed: {}", e))
+        })();
+
+        if let Err(e) = result {
+            e_exit("WINDOW", &e, 1);
+        }
+    }
+
+    /// Window (start, end) coordinates over a sequence, 0-based half-open
+    fn windows(len: usize, size: usize, step: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..len)
+            .step_by(step)
+            // Once a window reaches the end, later ones would only be its suffixes
+            .take_while(move |&start| start == 0 || start - step + size < len)
+            .map(move |start| (start, (start + size).min(len)))
+    }
+}
+
+/// Canonical k-mer counts of FASTA/FASTQ files
+///
+/// K-mers are packed 2 bits per base (k <= 31) and counted once per strand pair,
+/// as the smaller of the k-mer and its reverse complement. `U` is read as `T`,
+/// case is ignored and k-mers spanning any other character are skipped.
+pub struct InfoKmer;
+
+/// Packed k-mer to count
+pub type KmerCounts = HashMap<u64, u32>;
+
+impl InfoKmer {
+    /// Largest k that fits 2-bit packing in a u64 with a spare bit pair
+    pub const MAX_K: usize = 31;
+    /// Records counted in parallel at once
+    const BATCH_SIZE: usize = 10_000;
+
+    /// Counts the k-mers of the files, prints a summary and the top k-mers
+    ///
+    /// # Arguments
+    /// * `paths` - FASTA/FASTQ files, counted together
+    /// * `k` - K-mer length, 1 to [`InfoKmer::MAX_K`]
+    /// * `top` - Number of most frequent k-mers to print
+    /// * `table` - Optional TSV file for all k-mers and their counts
+    /// * `histo` - Optional TSV file for the k-mer spectrum (count, distinct k-mers)
+    pub fn report(paths: Vec<PathBuf>, k: usize, top: usize, table: Option<PathBuf>, histo: Option<PathBuf>) {
+        if k == 0 || k > Self::MAX_K {
+            e_exit("KMER", &format!("K must be between 1 and {}, got {}", Self::MAX_K, k), 1);
+        }
+
+        let counts = Self::count(&paths, k).unwrap_or_else(|e| e_exit("KMER", &e, 1));
+        let spectrum = Self::spectrum(&counts);
+        let total: u64 = counts.values().map(|&c| c as u64).sum();
+
+        println!("K-mer length : {}", k);
+        println!("Total k-mers : {}", total);
+        println!("Distinct k-mers : {}", counts.len());
+        println!("Unique k-mers (count 1) : {}", spectrum.get(&1).copied().unwrap_or(0));
+        match Self::estimate_genome_size(&spectrum) {
+            Some((peak, size)) => println!("Coverage peak / estimated genome size : {} / {}", peak, size),
+            None => println!("Coverage peak / estimated genome size : no peak found"),
+        }
+
+        if top > 0 {
+            let mut ranked: Vec<(&u64, &u32)> = counts.iter().collect();
+            ranked.sort_unstable_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let mut str_buf = vec!["K-mer\tCount\t\n".to_string()];
+            for (kmer, count) in ranked.into_iter().take(top) {
+                str_buf.push(format!("{}\t{}\t\n", Self::decode(*kmer, k), count));
+            }
+            println!("{}", format_table(str_buf.concat()));
+        }
+
+        if let Some(path) = table {
+            let mut sorted: Vec<(&u64, &u32)> = counts.iter().collect();
+            sorted.sort_unstable();
+            let rows = sorted.into_iter().map(|(kmer, count)| format!("{}\t{}", Self::decode(*kmer, k), count));
+            Self::write_tsv(&path, "kmer\tcount", rows).unwrap_or_else(|e| e_exit("KMER", &e, 1));
+        }
+        if let Some(path) = histo {
+            let rows = spectrum.iter().map(|(count, kmers)| format!("{}\t{}", count, kmers));
+            Self::write_tsv(&path, "count\tkmers", rows).unwrap_or_else(|e| e_exit("KMER", &e, 1));
+        }
+    }
+
+    /// Counts the canonical k-mers of all records of the files
+    pub fn count(paths: &[PathBuf], k: usize) -> Result<KmerCounts, String> {
+        let mut counts = KmerCounts::new();
+        for path in paths {
+            let mut records = utils::read_seq_records(path)?;
+            loop {
+                let batch = records.by_ref()
+                    .take(Self::BATCH_SIZE)
+                    .map(|r| r.map(|record| record.seq))
+                    .collect::<Result<Vec<_>, String>>()?;
+                if batch.is_empty() {
+                    break;
+                }
+
+                let batch_counts = batch.par_iter()
+                    .fold(KmerCounts::new, |mut counts, seq| {
+                        Self::count_seq(seq, k, &mut counts);
+                        counts
+                    })
+                    .reduce(KmerCounts::new, Self::merge);
+                counts = Self::merge(counts, batch_counts);
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Adds the canonical k-mers of one sequence to the counts
+    fn count_seq(seq: &[u8], k: usize, counts: &mut KmerCounts) {
+        let mask = (1u64 << (2 * k)) - 1;
+        let shift = 2 * (k - 1);
+        let (mut forward, mut reverse, mut len) = (0u64, 0u64, 0);
+        for &c in seq {
+            let code = match c.to_ascii_uppercase() {
+                b'A' => 0,
+                b'C' => 1,
+                b'G' => 2,
+                b'T' | b'U' => 3,
+                _ => {
+                    len = 0;
+                    continue;
+                }
+            };
+            forward = ((forward << 2) | code) & mask;
+            reverse = (reverse >> 2) | ((3 - code) << shift);
+            len += 1;
+            if len >= k {
+                *counts.entry(forward.min(reverse)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn merge(mut a: KmerCounts, mut b: KmerCounts) -> KmerCounts {
+        if a.len() < b.len() {
+            std::mem::swap(&mut a, &mut b);
+        }
+        for (kmer, count) in b {
+            *a.entry(kmer).or_insert(0) += count;
+        }
+        a
+    }
+
+    /// Unpacks a k-mer into its bases
+    pub fn decode(kmer: u64, k: usize) -> String {
+        (0..k).rev()
+            .map(|i| b"ACGT"[((kmer >> (2 * i)) & 3) as usize] as char)
+            .collect()
+    }
+
+    /// K-mer spectrum: for each count, the number of distinct k-mers seen that many times
+    pub fn spectrum(counts: &KmerCounts) -> BTreeMap<u32, u64> {
+        let mut spectrum = BTreeMap::new();
+        for &count in counts.values() {
+            *spectrum.entry(count).or_insert(0) += 1;
+        }
+        spectrum
+    }
+
+    /// Estimates the genome size from the spectrum, as the k-mers past the error valley over the coverage peak
+    ///
+    /// Returns the coverage peak and the estimated size, or `None` when the spectrum has no valley.
+    pub fn estimate_genome_size(spectrum: &BTreeMap<u32, u64>) -> Option<(u32, u64)> {
+        let frequency = |count: u32| spectrum.get(&count).copied().unwrap_or(0);
+        let max_count = *spectrum.keys().next_back()?;
+        let valley = (1..max_count).find(|&count| frequency(count) < frequency(count + 1))?;
+        let (peak, _) = spectrum.range(valley..).max_by_key(|(count, kmers)| (**kmers, std::cmp::Reverse(**count)))?;
+        let kmers: u64 = spectrum.range(valley..).map(|(&count, &kmers)| count as u64 * kmers).sum();
+        Some((*peak, kmers / *peak as u64))
+    }
+
+    fn write_tsv(path: &Path, header: &str, rows: impl Iterator<Item = String>) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", header).map_err(|e| format!("Write failed: {}", e))?;
+        for row in rows {
+            writeln!(writer, "{}", row).map_err(|e| format!("Write failed: {}", e))?;
+        }
+        writer.flush().map_err(|e| format!("Write failed: {}", e))
+    }
+}
+
+
 fn format_table(input: String) -> String {
     let rows: Vec<Vec<&str>> = input
         .split('\n')
@@ -13,27 +13,28 @@
 //! seq-here = "0.1.0"
 //! ```
 //!
-//! There are 3 modules in this crate for different purposes:
+//! There are 4 modules in this crate for different purposes:
 //! - **info**: Get basic information about the input sequence file(s).
 //! - **process**: Process incoming sequence file(s).
 //! - **extract**: Extract specified sequence segment or file data.
+//! - **validate**: Check sequence and annotation file(s) for errors.
 //!
 //! ## Examples
 //!
 //! - Info module:
 //!
-//! ```rust
+//! ```rust,no_run
 //! use seq_here::info::{self, InfoOutput};
 //! use std::path::{Path, PathBuf};
 //!
 //! let paths = vec![PathBuf::from("tests/test.fa")];
-//! info::InfoFa::by_println(paths.clone());
-//! info::InfoFa::by_file(paths);
+//! info::InfoFa::by_println(paths.clone(), vec![]);
+//! info::InfoFa::by_file(paths, vec![]);
 //! ```
 //!
 //! - Process module:
 //!
-//! ```rust
+//! ```rust,no_run
 //! use seq_here::process::{self};
 //! use std::path::PathBuf;
 //!
@@ -41,12 +42,17 @@
 //! let input_files = vec![PathBuf::from("file1.txt"), PathBuf::from("file2.txt")];
 //! let output_file = PathBuf::from("combined.txt");
 //! seq_here::process::ConvertCombine::combine_all(input_files, output_file);
+//!
+//! // Convert a GenBank file into GFF3 annotations
+//! let input_files = vec![PathBuf::from("record.gb")];
+//! let output_file = PathBuf::from("record.gff3");
+//! process::ConvertFormat::convert(input_files, output_file, process::ConvertTarget::Gff3, b'I', 60);
 //! ```
 //!
 //! - Extract module:
 //!
-//! ```rust
-//! use seq_here::extract::{ExtractSegment, ExtractExplain};
+//! ```rust,no_run
+//! use seq_here::extract::{ExplainFormat, ExtractSegment, ExtractExplain};
 //! use std::path::PathBuf;
 //!
 //! // Extract sequence by ID
@@ -55,10 +61,10 @@
 //! let id = "sequence_id".to_string();
 //! 
 //! // Extract full sequence matching the ID
-//! ExtractSegment::extract_id(input_files.clone(), id.clone(), output_file.clone(), None, None);
+//! ExtractSegment::extract_id(input_files.clone(), id.clone(), output_file.clone(), None, None, 60);
 //! 
 //! // Extract a specific segment (positions 10 to 50) from the sequence
-//! ExtractSegment::extract_id(input_files, id, output_file, Some(10), Some(50));
+//! ExtractSegment::extract_id(input_files, id, output_file, Some(10), Some(50), 60);
 //! 
 //! // Extract features from annotation files
 //! let seq_files = vec![PathBuf::from("genome.fasta")];
@@ -66,17 +72,23 @@
 //! let output_dir = PathBuf::from("extracted_features");
 //! 
 //! // Extract all annotated features
-//! ExtractExplain::extract(seq_files.clone(), anno_files.clone(), output_dir.clone(), None);
+//! ExtractExplain::extract(seq_files.clone(), anno_files.clone(), output_dir.clone(), None, ExplainFormat::Fasta, 60);
 //! 
 //! // Extract only CDS and gene features
 //! let feature_types = Some(vec!["CDS".to_string(), "gene".to_string()]);
-//! ExtractExplain::extract(seq_files, anno_files, output_dir, feature_types);
+//! ExtractExplain::extract(seq_files.clone(), anno_files.clone(), output_dir.clone(), feature_types, ExplainFormat::Fasta, 60);
+//!
+//! // Write the feature coordinates as BED instead
+//! ExtractExplain::extract(seq_files, anno_files, output_dir, None, ExplainFormat::Bed, 60);
 //! ```
 //!
 
 pub mod process;
 pub mod extract;
 pub mod info;
+pub mod validate;
 
 pub mod error;
 pub mod utils;
+pub mod flatfile;
+pub mod sam;
@@ -2,9 +2,11 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 use seq_here::error::e_exit;
 use seq_here::extract::{self};
-use seq_here::info::{self, InfoOutput};
+use seq_here::flatfile;
+use seq_here::info::{self, InfoOutput, OutputType};
 use seq_here::process::{self};
 use seq_here::utils;
+use seq_here::validate;
 use std::fs;
 use std::path::PathBuf;
 
@@ -19,6 +21,10 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    #[arg(long, global = true, default_value_t = utils::DEFAULT_LINE_WIDTH)]
+    #[arg(help = "Residues per line in FASTA output, 0 writes each sequence on one line")]
+    line_width: usize,
 }
 
 /// Commands List
@@ -36,6 +42,30 @@ enum Commands {
     #[command(subcommand)]
     #[command(about = "Extract specified sequence segments.")]
     Extract(ExtractCmd),
+
+    #[command(about = "Check FASTA/FASTQ/GFF/GTF file(s) and list issues as TSV, exits with 1 on errors.")]
+    Validate(ValidateArgs),
+}
+
+#[derive(Args)]
+struct ValidateArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(value_enum)]
+    #[arg(short = 'a', long)]
+    #[arg(help = "Declared sequence alphabet, by default each sequence may match any alphabet")]
+    alphabet: Option<SeqType>,
+
+    #[arg(short = 'r', long)]
+    #[arg(help = "FASTA/FASTQ file giving the sequence lengths for checking annotation coordinates")]
+    #[arg(value_name = "Reference")]
+    reference: Option<PathBuf>,
+
+    #[arg(short = 'o', long)]
+    #[arg(help = "Write the issue list to a file instead of stdout")]
+    #[arg(value_name = "OutputFile")]
+    output: Option<PathBuf>,
 }
 
 /// Info Subcommand
@@ -52,60 +82,659 @@ enum InfoCmd {
         about = "Gff/Gtf file information. Gff2 not supported yet due to upstream bio crate."
     )]
     Gff(InfoGffArgs),
+
+    #[command(about = "Bed file information: interval counts, total and covered length, per-chrom stats.")]
+    Bed(InfoBedArgs),
+
+    #[command(about = "GenBank/EMBL file information: per-record length, GC content, feature counts and definition.")]
+    Gb(InfoGbArgs),
+
+    #[command(about = "Sam/Bam file information: flag stats, mapped/unmapped counts and MAPQ distribution.")]
+    Sam(InfoSamArgs),
+
+    #[command(about = "Count canonical k-mers of Fasta/Fastq files, with top k-mers, count table and k-mer spectrum.")]
+    Kmer(InfoKmerArgs),
+
+    #[command(about = "Sliding-window GC content, GC skew, N density and soft-masked fraction as bedGraph or TSV.")]
+    Window(InfoWindowArgs),
+}
+
+#[derive(Args)]
+struct InfoFaArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'c', long)]
+    #[arg(help = "Show residue counts, GC skew, CpG o/e, soft-masking, N runs, entropy, and protein weight and pI")]
+    composition: bool,
+
+    #[arg(value_enum)]
+    #[arg(long, short = 'o', default_value = "println")]
+    output_type: OutputType,
+}
+
+#[derive(Args)]
+struct InfoFqArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'p', long)]
+    #[arg(help = "Treat the input files as mate pairs (R1,R2[,R1,R2...]) and check that read names match")]
+    paired: bool,
+
+    #[arg(long, short = 'o', default_value = "println")]
+    output_type: OutputType,
+}
+
+#[derive(Args)]
+struct InfoGffArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(long, short = 't', default_value = "gff3")]
+    _type: Option<String>,
+
+    #[arg(long, short = 'o', default_value = "println")]
+    output_type: OutputType,
+}
+
+#[derive(Args)]
+struct InfoBedArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(long, short = 'o', default_value = "println")]
+    output_type: OutputType,
+}
+
+#[derive(Args)]
+struct InfoGbArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(long, short = 'o', default_value = "println")]
+    output_type: OutputType,
+}
+
+#[derive(Args)]
+struct InfoSamArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(long, short = 'o', default_value = "println")]
+    output_type: OutputType,
+}
+
+#[derive(Args)]
+struct InfoKmerArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'k', long, default_value = "21")]
+    #[arg(help = "K-mer length, at most 31")]
+    k: usize,
+
+    #[arg(short = 'n', long, default_value = "10")]
+    #[arg(help = "Number of most frequent k-mers to print")]
+    top: usize,
+
+    #[arg(short = 't', long)]
+    #[arg(help = "Write all k-mers and their counts to this TSV file")]
+    table: Option<PathBuf>,
+
+    #[arg(short = 's', long)]
+    #[arg(help = "Write the k-mer spectrum (count, distinct k-mers) to this TSV file, e.g. for genome size estimation")]
+    histo: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct InfoWindowArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'w', long, default_value = "1000")]
+    #[arg(help = "Window size in bases")]
+    size: usize,
+
+    #[arg(short = 's', long)]
+    #[arg(help = "Step between window starts, default the window size (no overlap)")]
+    step: Option<usize>,
+
+    #[arg(short = 't', long, value_enum)]
+    #[arg(help = "Write this statistic as a bedGraph track; without it all statistics are written as TSV")]
+    stat: Option<WindowStatArg>,
+
+    #[arg(short = 'o', long)]
+    #[arg(help = "Output file, default standard output")]
+    #[arg(value_name = "OutputFile")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum WindowStatArg {
+    Gc,
+    GcSkew,
+    NDensity,
+    SoftMasked,
+}
+
+/// Process Subcommand
+///
+#[derive(Subcommand)]
+enum ProcessCmd {
+    #[command(about = "Combine the given files into one file, support all-type text files. \
+    Use --records to combine FASTA/FASTQ/GFF files record by record.")]
+    Combine(ProcessCombineArgs),
+
+    #[command(about = "Convert files between formats: FASTQ<->FASTA, GenBank/EMBL->FASTA/GFF3, GFF3<->GTF, GFF3/GTF<->BED.")]
+    Convert(ProcessConvertArgs),
+
+    #[command(about = "Reverse-complement, complement or reverse FASTA/FASTQ sequences (IUPAC and RNA aware).")]
+    Revcomp(ProcessRevcompArgs),
+
+    #[command(about = "Filter FASTA/FASTQ records by length, GC content, N content, quality or sequence type.")]
+    Filter(ProcessFilterArgs),
+
+    #[command(about = "Remove duplicate FASTA/FASTQ records by ID, sequence or canonical sequence. \
+    Memory grows by about 16 bytes per distinct record, plus the kept IDs with --map.")]
+    Dedup(ProcessDedupArgs),
+
+    #[command(about = "Sort FASTA/FASTQ records by ID, natural ID, length or a given order; \
+    sort GFF3/GTF features by position with parents before children.")]
+    Sort(ProcessSortArgs),
+
+    #[command(about = "Split FASTA/FASTQ files into parts by count of parts, records or bytes, or one file per record.")]
+    Split(ProcessSplitArgs),
+
+    #[command(about = "Randomly sample a fixed number or a fraction of FASTA/FASTQ records, optionally as read pairs.")]
+    Sample(ProcessSampleArgs),
+
+    #[command(about = "Interleave paired-end mate files (R1,R2) into one file, checking that read names match.")]
+    Interleave(ProcessInterleaveArgs),
+
+    #[command(about = "Split interleaved paired-end files into R1 and R2 mate files.")]
+    Deinterleave(ProcessDeinterleaveArgs),
+
+    #[command(about = "Re-pair out-of-sync paired-end mate files by read name.")]
+    Repair(ProcessRepairArgs),
+
+    #[command(about = "Trim reads: head/tail clipping, adapters, poly-G/poly-A tails and low-quality ends.")]
+    Trim(ProcessTrimArgs),
+
+    #[command(about = "Rename FASTA/FASTQ IDs or GFF/GTF seqnames from a mapping file, a template or a regex.")]
+    Rename(ProcessRenameArgs),
+
+    #[command(about = "Rewrite FASTA/FASTQ files: line width (--line-width), letter case, \
+    stray whitespace/digits and CRLF line endings.")]
+    Reformat(ProcessReformatArgs),
+
+    #[command(about = "Mask low-complexity regions (DUST), BED/GFF intervals and homopolymer runs, soft or with N.")]
+    Mask(ProcessMaskArgs),
+
+    #[command(about = "Remove soft-masking by upper-casing sequences.")]
+    Unmask(ProcessUnmaskArgs),
+}
+
+#[derive(Args)]
+struct ProcessCombineArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'r', long)]
+    #[arg(help = "Parse and rewrite each record instead of concatenating bytes, \
+     keeping the input order and writing GFF directives once")]
+    records: bool,
+
+    #[arg(short = 'p', long, requires = "records")]
+    #[arg(help = "Prefix sequence IDs with the source file name (with --records)")]
+    prefix_ids: bool,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Args)]
+struct ProcessConvertArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(value_enum)]
+    #[arg(short = 't', long = "to")]
+    #[arg(help = "Output format")]
+    to: ConvertTo,
+
+    #[arg(short = 'q', long, default_value = "I")]
+    #[arg(help = "Quality character (Phred+33) used when converting FASTA to FASTQ")]
+    quality: char,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ConvertTo {
+    Fasta,
+    Fastq,
+    Gff3,
+    Gtf,
+    Bed,
+}
+
+#[derive(Args)]
+struct ProcessRevcompArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(value_enum)]
+    #[arg(short = 'm', long, default_value = "revcomp")]
+    #[arg(help = "Operation to apply")]
+    mode: RevcompMode,
+
+    #[command(flatten)]
+    output: OutputFile,
 }
 
-#[derive(Args)]
-struct InfoFaArgs {
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RevcompMode {
+    Revcomp,
+    Complement,
+    Reverse,
+}
+
+#[derive(Args)]
+struct ProcessFilterArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(long)]
+    #[arg(help = "Minimum sequence length")]
+    min_len: Option<usize>,
+
+    #[arg(long)]
+    #[arg(help = "Maximum sequence length")]
+    max_len: Option<usize>,
+
+    #[arg(long)]
+    #[arg(help = "Minimum GC content, as a fraction between 0 and 1")]
+    min_gc: Option<f32>,
+
+    #[arg(long)]
+    #[arg(help = "Maximum GC content, as a fraction between 0 and 1")]
+    max_gc: Option<f32>,
+
+    #[arg(long)]
+    #[arg(help = "Maximum fraction of N bases, between 0 and 1")]
+    max_n_frac: Option<f32>,
+
+    #[arg(long)]
+    #[arg(help = "Minimum mean Phred quality (FASTQ only)")]
+    min_mean_qual: Option<f64>,
+
+    #[arg(value_enum)]
+    #[arg(long)]
+    #[arg(help = "Keep only sequences of this type")]
+    seq_type: Option<SeqType>,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SeqType {
+    Dna,
+    Rna,
+    Protein,
+}
+
+impl SeqType {
+    fn alphabet(self) -> utils::Alphabet {
+        match self {
+            SeqType::Dna => utils::Alphabet::Dna,
+            SeqType::Rna => utils::Alphabet::Rna,
+            SeqType::Protein => utils::Alphabet::Protein,
+        }
+    }
+}
+
+#[derive(Args)]
+struct ProcessDedupArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(value_enum)]
+    #[arg(short = 'b', long, default_value = "seq")]
+    #[arg(help = "Duplicate criterion, canonical also matches reverse complements")]
+    by: DedupBy,
+
+    #[arg(short = 'm', long)]
+    #[arg(help = "Write a TSV mapping of kept ID to removed ID")]
+    #[arg(value_name = "MapFile")]
+    map: Option<PathBuf>,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DedupBy {
+    Id,
+    Seq,
+    Canonical,
+}
+
+#[derive(Args)]
+struct ProcessSortArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(value_enum)]
+    #[arg(short = 'b', long, default_value = "natural")]
+    #[arg(help = "Sort key for FASTA/FASTQ records, ignored for GFF3/GTF files")]
+    by: SortBy,
+
+    #[arg(long, required_if_eq("by", "order"))]
+    #[arg(help = "File with one ID per line giving the order, used with --by order")]
+    #[arg(value_name = "OrderFile")]
+    order_file: Option<PathBuf>,
+
+    #[arg(short = 'r', long)]
+    #[arg(help = "Sort in descending order")]
+    reverse: bool,
+
+    #[arg(short = 'm', long, default_value_t = 1024)]
+    #[arg(help = "Memory budget in MB before sorted chunks are spilled to disk")]
+    max_memory: usize,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SortBy {
+    Name,
+    Natural,
+    Length,
+    Order,
+}
+
+#[derive(Args)]
+struct ProcessSplitArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[command(flatten)]
+    split_by: SplitBy,
+
+    #[arg(short = 'n', long)]
+    #[arg(help = "Output name template with {stem}, {ext}, {num} (part number) and {id} (record ID), \
+     default '{stem}.part_{num}.{ext}', or '{id}.{ext}' with --by-id")]
+    #[arg(value_name = "Template")]
+    name: Option<String>,
+
+    #[arg(short = 'o', long)]
+    #[arg(help = "Output directory for the parts, default './split'")]
+    #[arg(value_name = "OutputDir")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct SplitBy {
+    #[arg(short = 'p', long)]
+    #[arg(help = "Split into N parts, records are dealt out round-robin")]
+    parts: Option<usize>,
+
+    #[arg(short = 'r', long)]
+    #[arg(help = "Split into parts of at most N records")]
+    records: Option<usize>,
+
+    #[arg(short = 'b', long)]
+    #[arg(help = "Split into parts of at most N bytes")]
+    bytes: Option<u64>,
+
+    #[arg(long)]
+    #[arg(help = "Write one file per record, named by record ID")]
+    by_id: bool,
+}
+
+#[derive(Args)]
+struct ProcessSampleArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[command(flatten)]
+    size: SampleBy,
+
+    #[arg(short = 's', long)]
+    #[arg(help = "Random seed, sampling with the same seed gives the same records")]
+    seed: Option<u64>,
+
+    #[arg(short = 'p', long)]
+    #[arg(help = "Treat the input files as mate pairs (R1,R2[,R1,R2...]) and sample the same pairs \
+     from both mates; the output is then a directory")]
+    paired: bool,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct SampleBy {
+    #[arg(short = 'n', long)]
+    #[arg(help = "Number of records (or pairs) to sample")]
+    number: Option<usize>,
+
+    #[arg(short = 'f', long)]
+    #[arg(help = "Fraction of records (or pairs) to sample, between 0 and 1")]
+    fraction: Option<f64>,
+}
+
+#[derive(Args)]
+struct ProcessInterleaveArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Args)]
+struct ProcessDeinterleaveArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'o', long)]
+    #[arg(help = "Output directory, each input is written to {stem}_1.{ext} and {stem}_2.{ext}, default './deinterleaved'")]
+    #[arg(value_name = "OutputDir")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ProcessTrimArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(long, default_value_t = 0)]
+    #[arg(help = "Clip N bases from the 5' end of every read")]
+    head: usize,
+
+    #[arg(long, default_value_t = 0)]
+    #[arg(help = "Clip N bases from the 3' end of every read")]
+    tail: usize,
+
+    #[arg(short = 'a', long)]
+    #[arg(help = "FASTA file of adapter sequences, reads are cut at the first adapter match")]
+    #[arg(value_name = "AdapterFile")]
+    adapters: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 2)]
+    #[arg(help = "Mismatches allowed in a full-length adapter match, fewer for partial matches at the 3' end")]
+    adapter_mismatches: usize,
+
+    #[arg(long, default_value_t = 3)]
+    #[arg(help = "Minimum adapter overlap at the 3' end of a read")]
+    min_overlap: usize,
+
+    #[arg(long)]
+    #[arg(help = "Trim poly-G tails of at least N bases (two-colour chemistry artefact)")]
+    poly_g: Option<usize>,
+
+    #[arg(long)]
+    #[arg(help = "Trim poly-A tails of at least N bases")]
+    poly_a: Option<usize>,
+
+    #[arg(long)]
+    #[arg(help = "Trim bases below this Phred quality from the 5' end")]
+    leading: Option<u8>,
+
+    #[arg(long)]
+    #[arg(help = "Trim bases below this Phred quality from the 3' end")]
+    trailing: Option<u8>,
+
+    #[arg(short = 'w', long, requires = "window_qual")]
+    #[arg(help = "Sliding window size, reads are cut at the first window below --window-qual")]
+    window: Option<usize>,
+
+    #[arg(long, requires = "window")]
+    #[arg(help = "Minimum mean Phred quality of a sliding window")]
+    window_qual: Option<f64>,
+
+    #[arg(short = 'l', long, default_value_t = 1)]
+    #[arg(help = "Remove reads shorter than this after trimming")]
+    min_len: usize,
+
+    #[arg(value_enum)]
+    #[arg(long, default_value = "println")]
+    #[arg(help = "Output type of the per-file trimming report, as in `info fq`")]
+    report: OutputType,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Args)]
+struct ProcessRenameArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[command(flatten)]
+    rename_by: RenameBy,
+
+    #[arg(long, requires = "regex", default_value = "")]
+    #[arg(help = "Replacement for --regex matches, $1 or ${name} refer to capture groups")]
+    replace: String,
+
+    #[arg(short = 'k', long)]
+    #[arg(help = "Keep the old ID at the start of the description (FASTA/FASTQ)")]
+    keep_old: bool,
+
+    #[arg(long)]
+    #[arg(help = "Write the applied renames as old<TAB>new lines, usable with --map for annotation files")]
+    #[arg(value_name = "MapFile")]
+    map_out: Option<PathBuf>,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct RenameBy {
+    #[arg(short = 'm', long)]
+    #[arg(help = "TSV file of old<TAB>new IDs, unlisted IDs are kept")]
+    #[arg(value_name = "MapFile")]
+    map: Option<PathBuf>,
+
+    #[arg(short = 't', long)]
+    #[arg(help = "ID template with {file}, {num} (record number per file), {id} and {len}, e.g. '{file}_{num}'")]
+    template: Option<String>,
+
+    #[arg(short = 'r', long)]
+    #[arg(help = "Regex applied to IDs, matches are replaced with --replace")]
+    regex: Option<String>,
+}
+
+#[derive(Args)]
+struct ProcessReformatArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(value_enum)]
+    #[arg(short = 'c', long, default_value = "preserve")]
+    #[arg(help = "Letter case of the sequences, preserve keeps soft-masked regions")]
+    case: Case,
+
+    #[arg(short = 's', long)]
+    #[arg(help = "Remove whitespace and digits inside sequence lines")]
+    strip: bool,
+
     #[command(flatten)]
-    input: InputFile,
-    #[arg(value_enum)]
-    #[arg(long, short = 'o', default_value = "println")]
-    output_type: OutputType,
+    output: OutputFile,
 }
 
 #[derive(Args)]
-struct InfoFqArgs {
+struct ProcessMaskArgs {
     #[command(flatten)]
     input: InputFile,
 
-    #[arg(long, short = 'o', default_value = "println")]
-    output_type: OutputType,
+    #[arg(short = 'd', long)]
+    #[arg(help = "Mask low-complexity regions with DUST")]
+    dust: bool,
+
+    #[arg(long, default_value_t = process::SeqMask::DUST_WINDOW)]
+    #[arg(help = "DUST window size")]
+    dust_window: usize,
+
+    #[arg(long, default_value_t = process::SeqMask::DUST_LEVEL)]
+    #[arg(help = "DUST score threshold, lower values mask more")]
+    dust_level: f64,
+
+    #[arg(short = 'i', long)]
+    #[arg(help = "Mask the intervals of a BED (0-based) or GFF3/GTF (1-based) file")]
+    intervals: Option<PathBuf>,
+
+    #[arg(short = 'p', long)]
+    #[arg(help = "Mask homopolymer runs of at least this length")]
+    homopolymer: Option<usize>,
+
+    #[arg(long)]
+    #[arg(help = "Replace masked bases with N instead of lower-casing them")]
+    hard: bool,
+
+    #[command(flatten)]
+    output: OutputFile,
 }
 
 #[derive(Args)]
-struct InfoGffArgs {
+struct ProcessUnmaskArgs {
     #[command(flatten)]
     input: InputFile,
 
-    #[arg(long, short = 't', default_value = "gff3")]
-    _type: Option<String>,
-
-    #[arg(long, short = 'o', default_value = "println")]
-    output_type: OutputType,
+    #[command(flatten)]
+    output: OutputFile,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
-enum OutputType {
-    File,
-    Println,
-    Csv,
-}
-
-/// Process Subcommand
-///
-#[derive(Subcommand)]
-enum ProcessCmd {
-    #[command(about = "Combine the given files into one file, support all-type text files.(TODO)")]
-    Combine(ProcessCombineArgs),
+enum Case {
+    Preserve,
+    Upper,
+    Lower,
 }
 
 #[derive(Args)]
-struct ProcessCombineArgs {
+struct ProcessRepairArgs {
     #[command(flatten)]
     input: InputFile,
 
-    #[command(flatten)]
-    output: OutputFile,
+    #[arg(short = 's', long)]
+    #[arg(help = "Write reads without a mate to {stem}.singletons.{ext}")]
+    singletons: bool,
+
+    #[arg(short = 'o', long)]
+    #[arg(help = "Output directory, mates are written under their input file names, default './repaired'")]
+    #[arg(value_name = "OutputDir")]
+    output: Option<PathBuf>,
 }
 
 /// Extract Subcommand
@@ -117,6 +746,15 @@ enum ExtractCmd {
 
     #[command(about = "Extract sequence segment from fasta file by given gff file.")]
     Explain(ExtractExplainArgs),
+
+    #[command(about = "Find exact, IUPAC or regex motifs on both strands, reporting hits as BED or GFF3.")]
+    Motif(ExtractMotifArgs),
+
+    #[command(about = "In-silico PCR: find primer pair products within a maximum amplicon size and extract the amplicons.")]
+    Pcr(ExtractPcrArgs),
+
+    #[command(about = "Find open reading frames in six frames, writing nucleotide and protein Fasta plus GFF3.")]
+    Orf(ExtractOrfArgs),
 }
 
 #[derive(Args)]
@@ -127,7 +765,7 @@ struct ExtractSegmentArgs {
     #[command(flatten)]
     id_options: InputOptions,
 
-    #[arg(short, long)]
+    #[arg(long)]
     #[arg(help = "Optional start position (0-based) for the extracted segment")]
     start: Option<usize>,
 
@@ -135,6 +773,17 @@ struct ExtractSegmentArgs {
     #[arg(help = "Optional end position (0-based, exclusive) for the extracted segment")]
     end: Option<usize>,
 
+    #[arg(short = 'r', long, group = "InputOptions", conflicts_with_all = ["start", "end", "paired"])]
+    #[arg(help = "Extract the Sam/Bam reads overlapping a region (name[:start[-end]], 1-based inclusive) \
+     instead of by id; reads are written as Fastq, or Fasta for a .fa/.fasta output")]
+    #[arg(value_name = "Region")]
+    region: Option<String>,
+
+    #[arg(short = 'p', long, conflicts_with_all = ["start", "end"])]
+    #[arg(help = "Treat the input files as mate pairs (R1,R2[,R1,R2...]) and extract both mates; \
+     the output is then a directory")]
+    paired: bool,
+
     #[command(flatten)]
     output: OutputFile,
 }
@@ -143,14 +792,13 @@ struct ExtractSegmentArgs {
 struct ExtractExplainArgs {
     #[arg(short = 's', long = "seq")]
     #[arg(required = true)]
-    #[arg(help = "Input sequence files (FASTA), separated by ',' .")]
+    #[arg(help = "Input sequence files (FASTA, or GenBank/EMBL with their own features), separated by ',' .")]
     #[arg(value_name = "SEQ_FILES")]
     #[arg(value_delimiter = ',')]
     seq_files: Vec<PathBuf>,
 
     #[arg(short = 'g', long = "gff")]
-    #[arg(required = true)]
-    #[arg(help = "Input annotation files (GFF/GTF), separated by ',' .")]
+    #[arg(help = "Input annotation files (GFF/GTF/GenBank/EMBL), separated by ',' . Optional when all sequence files are GenBank/EMBL")]
     #[arg(value_name = "GFF_FILES")]
     #[arg(value_delimiter = ',')]
     gff_files: Vec<PathBuf>,
@@ -161,10 +809,142 @@ struct ExtractExplainArgs {
     #[arg(value_delimiter = ',')]
     feature_types: Option<Vec<String>>,
 
+    #[arg(long, value_enum, default_value = "fasta")]
+    #[arg(help = "Write the feature sequences, or their coordinates as BED")]
+    format: ExplainFormat,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExplainFormat {
+    Fasta,
+    Bed,
+}
+
+#[derive(Args)]
+struct ExtractMotifArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[command(flatten)]
+    motif_options: InputOptions,
+
+    #[arg(short = 't', long = "type", value_enum, default_value = "iupac")]
+    #[arg(help = "How motifs are matched; a motif file has one motif per line, as 'pattern' or 'name<TAB>pattern'")]
+    kind: MotifKind,
+
+    #[arg(long, value_enum, default_value = "bed")]
+    #[arg(help = "Output format of the hits")]
+    format: MotifFormat,
+
+    #[arg(short = 'm', long, default_value = "0")]
+    #[arg(help = "Maximum number of mismatches (or edits with --indels), for exact and IUPAC motifs")]
+    mismatches: usize,
+
+    #[arg(long)]
+    #[arg(help = "Also allow insertions and deletions, motifs are then limited to 64 bases")]
+    indels: bool,
+
+    #[arg(long)]
+    #[arg(help = "Only search the forward strand")]
+    forward: bool,
+
+    #[arg(long)]
+    #[arg(help = "Also write the hit sequences with this many flanking bases to '<output>.fa'")]
+    flank: Option<usize>,
+
+    #[command(flatten)]
+    output: OutputFile,
+}
+
+#[derive(Args)]
+struct ExtractPcrArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'f', long = "forward-primer")]
+    #[arg(help = "Forward primer, 5' to 3', IUPAC codes allowed")]
+    forward: String,
+
+    #[arg(short = 'r', long = "reverse-primer")]
+    #[arg(help = "Reverse primer, 5' to 3', IUPAC codes allowed")]
+    reverse: String,
+
+    #[arg(short = 'l', long, default_value = "3000")]
+    #[arg(help = "Maximum amplicon size, primers included")]
+    max_size: usize,
+
+    #[arg(short = 'm', long, default_value = "0")]
+    #[arg(help = "Maximum number of mismatches (or edits with --indels) per primer")]
+    mismatches: usize,
+
+    #[arg(long)]
+    #[arg(help = "Also allow insertions and deletions in the primer sites")]
+    indels: bool,
+
+    #[arg(long)]
+    #[arg(help = "Also write the amplicon coordinates to this BED file")]
+    bed: Option<PathBuf>,
+
     #[command(flatten)]
     output: OutputFile,
 }
 
+#[derive(Args)]
+struct ExtractOrfArgs {
+    #[command(flatten)]
+    input: InputFile,
+
+    #[arg(short = 'l', long, default_value = "75")]
+    #[arg(help = "Minimum ORF length in bases, stop codon included")]
+    min_len: usize,
+
+    #[arg(short = 'g', long = "code", default_value = "1")]
+    #[arg(help = "NCBI genetic code: 1, 2, 3, 4, 5, 6 or 11")]
+    code: u8,
+
+    #[arg(long, value_enum, default_value = "atg")]
+    #[arg(help = "Start codons: ATG only, the alternative starts of the genetic code, or any (stop to stop)")]
+    starts: OrfStarts,
+
+    #[arg(long, value_enum, default_value = "longest")]
+    #[arg(help = "Nested ORFs: only the longest per stop codon, or one per in-frame start")]
+    nested: NestedOrfs,
+
+    #[arg(short = 'o', long)]
+    #[arg(help = "Output directory for orfs.fna, orfs.faa and orfs.gff3, default './orfs'")]
+    #[arg(value_name = "OutputDir")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OrfStarts {
+    Atg,
+    Alternative,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum NestedOrfs {
+    Longest,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MotifKind {
+    Exact,
+    Iupac,
+    Regex,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MotifFormat {
+    Bed,
+    Gff3,
+}
+
 /// I/O Options
 ///
 #[derive(Args)]
@@ -266,25 +1046,72 @@ fn handle_info_output<T: InfoOutput>(files: Vec<PathBuf>, output_type: OutputTyp
     }
 }
 
+/// Groups input files as (R1, R2) mate pairs, exiting on an odd number of files
+fn mate_pairs(files: Vec<PathBuf>) -> Vec<(PathBuf, PathBuf)> {
+    if files.len() % 2 != 0 {
+        e_exit("ARGS", "Paired-end input requires an even number of files (R1,R2 pairs).", 1);
+    }
+    files.chunks(2).map(|pair| (pair[0].clone(), pair[1].clone())).collect()
+}
+
 fn main() {
     let args = Cli::parse();
+    let line_width = args.line_width;
 
     match args.command {
         Commands::Info(info_cmd) => match info_cmd {
             InfoCmd::Fa(args) => {
                 let files = args.input.get_files();
-                handle_info_output::<info::InfoFa>(files, args.output_type, vec![]);
+                let extra_args = if args.composition { vec!["composition".to_string()] } else { vec![] };
+                handle_info_output::<info::InfoFa>(files, args.output_type, extra_args);
             }
 
             InfoCmd::Fq(args) => {
                 let files = args.input.get_files();
-                handle_info_output::<info::InfoFq>(files, args.output_type, vec![]);
+                let extra_args = if args.paired { vec!["paired".to_string()] } else { vec![] };
+                handle_info_output::<info::InfoFq>(files, args.output_type, extra_args);
             }
 
             InfoCmd::Gff(args) => {
                 let files = args.input.get_files();
                 handle_info_output::<info::InfoGff>(files, args.output_type, vec!["gff3".to_string()]);
             }
+
+            InfoCmd::Bed(args) => {
+                let files = args.input.get_files();
+                handle_info_output::<info::InfoBed>(files, args.output_type, vec![]);
+            }
+
+            InfoCmd::Gb(args) => {
+                let files = args.input.get_files();
+                handle_info_output::<info::InfoGb>(files, args.output_type, vec![]);
+            }
+
+            InfoCmd::Sam(args) => {
+                let files = args.input.get_files();
+                handle_info_output::<info::InfoSam>(files, args.output_type, vec![]);
+            }
+
+            InfoCmd::Kmer(args) => {
+                let files = args.input.get_files();
+                println!("{}: {:?}", "Inputs:".green().bold(), files);
+                info::InfoKmer::report(files, args.k, args.top, args.table, args.histo);
+            }
+
+            InfoCmd::Window(args) => {
+                let files = args.input.get_files();
+                let stat = args.stat.map(|stat| match stat {
+                    WindowStatArg::Gc => info::WindowStat::Gc,
+                    WindowStatArg::GcSkew => info::WindowStat::GcSkew,
+                    WindowStatArg::NDensity => info::WindowStat::NDensity,
+                    WindowStatArg::SoftMasked => info::WindowStat::SoftMasked,
+                });
+                // Keep standard output clean when the track is written there
+                if args.output.is_some() {
+                    println!("{}: {:?}", "Inputs:".green().bold(), files);
+                }
+                info::InfoWindow::report(files, args.size, args.step.unwrap_or(args.size), stat, args.output);
+            }
         },
 
         Commands::Process(process_cmd) => match process_cmd {
@@ -293,14 +1120,285 @@ fn main() {
                 let out = args.output.get_file("./combined");
                 println!("{}: {:?}", "Input files:".green().bold(), files);
                 println!("{}: {:?}", "Output file:".green().bold(), out);
-                process::ConvertCombine::combine_all(files, out);
+                if args.records {
+                    process::ConvertCombine::combine_records(files, out, args.prefix_ids, line_width);
+                } else {
+                    process::ConvertCombine::combine_all(files, out);
+                }
+            }
+
+            ProcessCmd::Convert(args) => {
+                let files = args.input.get_files();
+                let (target, default) = match args.to {
+                    ConvertTo::Fasta => (process::ConvertTarget::Fasta, "./converted.fasta"),
+                    ConvertTo::Fastq => (process::ConvertTarget::Fastq, "./converted.fastq"),
+                    ConvertTo::Gff3 => (process::ConvertTarget::Gff3, "./converted.gff3"),
+                    ConvertTo::Gtf => (process::ConvertTarget::Gtf, "./converted.gtf"),
+                    ConvertTo::Bed => (process::ConvertTarget::Bed, "./converted.bed"),
+                };
+                if !('!'..='~').contains(&args.quality) {
+                    e_exit("ARGS", "Quality must be a printable ASCII character ('!' to '~').", 1);
+                }
+                let out = args.output.get_file(default);
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::ConvertFormat::convert(files, out, target, args.quality as u8, line_width);
+            }
+
+            ProcessCmd::Revcomp(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./revcomp");
+                let mode = match args.mode {
+                    RevcompMode::Revcomp => process::RevCompMode::ReverseComplement,
+                    RevcompMode::Complement => process::RevCompMode::Complement,
+                    RevcompMode::Reverse => process::RevCompMode::Reverse,
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::RevComp::revcomp(files, out, mode, line_width);
+            }
+
+            ProcessCmd::Filter(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./filtered");
+                for fraction in [args.min_gc, args.max_gc, args.max_n_frac].into_iter().flatten() {
+                    if !(0.0..=1.0).contains(&fraction) {
+                        e_exit("ARGS", "GC and N fractions must be between 0 and 1.", 1);
+                    }
+                }
+                let options = process::FilterOptions {
+                    min_len: args.min_len,
+                    max_len: args.max_len,
+                    min_gc: args.min_gc,
+                    max_gc: args.max_gc,
+                    max_n_frac: args.max_n_frac,
+                    min_mean_qual: args.min_mean_qual,
+                    seq_type: args.seq_type.map(SeqType::alphabet),
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqFilter::filter(files, out, &options, line_width);
+            }
+
+            ProcessCmd::Dedup(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./dedup");
+                let key = match args.by {
+                    DedupBy::Id => process::DedupKey::Id,
+                    DedupBy::Seq => process::DedupKey::Sequence,
+                    DedupBy::Canonical => process::DedupKey::Canonical,
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqDedup::dedup(files, out, key, args.map, line_width);
+            }
+
+            ProcessCmd::Sort(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./sorted");
+                let key = match args.by {
+                    SortBy::Name => process::SortKey::Name,
+                    SortBy::Natural => process::SortKey::Natural,
+                    SortBy::Length => process::SortKey::Length,
+                    SortBy::Order => {
+                        let order_file = args.order_file.unwrap_or_default();
+                        let content = fs::read_to_string(&order_file).unwrap_or_else(|e| {
+                            e_exit("FILE-READ", &format!("Failed to read order file {:?}: {}", order_file, e), 1)
+                        });
+                        process::SortKey::Order(
+                            content.lines()
+                                .map(|l| l.trim())
+                                .filter(|l| !l.is_empty())
+                                .map(String::from)
+                                .collect(),
+                        )
+                    }
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqSort::sort(files, out, key, args.reverse, args.max_memory.max(1) * 1024 * 1024, line_width);
+            }
+
+            ProcessCmd::Split(args) => {
+                let files = args.input.get_files();
+                let out = args.output.unwrap_or_else(|| PathBuf::from("./split"));
+                let by = args.split_by;
+                let mode = match (by.parts, by.records, by.bytes) {
+                    (Some(n), _, _) => process::SplitMode::Parts(n),
+                    (_, Some(n), _) => process::SplitMode::Records(n),
+                    (_, _, Some(n)) => process::SplitMode::Bytes(n),
+                    _ => process::SplitMode::ById,
+                };
+                if matches!(mode, process::SplitMode::Parts(0) | process::SplitMode::Records(0) | process::SplitMode::Bytes(0)) {
+                    e_exit("ARGS", "--parts, --records and --bytes must be greater than 0.", 1);
+                }
+                let template = args.name.unwrap_or_else(|| match mode {
+                    process::SplitMode::ById => process::SPLIT_TEMPLATE_BY_ID.to_string(),
+                    _ => process::SPLIT_TEMPLATE.to_string(),
+                });
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output directory:".green().bold(), out);
+                process::SeqSplit::split(files, out, mode, &template, line_width);
+            }
+
+            ProcessCmd::Sample(args) => {
+                let files = args.input.get_files();
+                let size = match (args.size.number, args.size.fraction) {
+                    (Some(n), _) => process::SampleSize::Count(n),
+                    (_, Some(f)) if (0.0..=1.0).contains(&f) => process::SampleSize::Fraction(f),
+                    _ => e_exit("ARGS", "Fraction must be between 0 and 1.", 1),
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+
+                if args.paired {
+                    let out = args.output.output.unwrap_or_else(|| PathBuf::from("./sampled"));
+                    println!("{}: {:?}", "Output directory:".green().bold(), out);
+                    for (r1, r2) in mate_pairs(files) {
+                        process::SeqSample::sample_paired(&r1, &r2, &out, size, args.seed, line_width);
+                    }
+                } else {
+                    let out = args.output.get_file("./sampled");
+                    println!("{}: {:?}", "Output file:".green().bold(), out);
+                    process::SeqSample::sample(files, out, size, args.seed, line_width);
+                }
+            }
+
+            ProcessCmd::Interleave(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./interleaved");
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::PairedEnd::interleave(mate_pairs(files), out, line_width);
+            }
+
+            ProcessCmd::Deinterleave(args) => {
+                let files = args.input.get_files();
+                let out = args.output.unwrap_or_else(|| PathBuf::from("./deinterleaved"));
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output directory:".green().bold(), out);
+                process::PairedEnd::deinterleave(files, out, line_width);
+            }
+
+            ProcessCmd::Repair(args) => {
+                let files = args.input.get_files();
+                let out = args.output.unwrap_or_else(|| PathBuf::from("./repaired"));
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output directory:".green().bold(), out);
+                for (r1, r2) in mate_pairs(files) {
+                    process::PairedEnd::repair(&r1, &r2, &out, args.singletons, line_width);
+                }
+            }
+
+            ProcessCmd::Rename(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./renamed");
+                let by = args.rename_by;
+                let rule = match (by.map, by.template, by.regex) {
+                    (Some(path), _, _) => {
+                        let content = fs::read_to_string(&path).unwrap_or_else(|e| {
+                            e_exit("FILE-READ", &format!("Failed to read mapping file {:?}: {}", path, e), 1)
+                        });
+                        process::RenameRule::Mapping(
+                            content.lines()
+                                .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+                                .filter_map(|l| {
+                                    let mut fields = l.split('\t').map(str::trim);
+                                    Some((fields.next()?.to_string(), fields.next()?.to_string()))
+                                })
+                                .collect(),
+                        )
+                    }
+                    (_, Some(template), _) => process::RenameRule::Template(template),
+                    (_, _, Some(pattern)) => match regex::Regex::new(&pattern) {
+                        Ok(re) => process::RenameRule::Regex(re, args.replace),
+                        Err(e) => e_exit("ARGS", &format!("Invalid regex: {}", e), 1),
+                    },
+                    _ => unreachable!(),
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqRename::rename(files, out, &rule, args.keep_old, args.map_out, line_width);
+            }
+
+            ProcessCmd::Reformat(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./reformatted");
+                let case = match args.case {
+                    Case::Preserve => process::CaseMode::Preserve,
+                    Case::Upper => process::CaseMode::Upper,
+                    Case::Lower => process::CaseMode::Lower,
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqReformat::reformat(files, out, case, args.strip, line_width);
+            }
+
+            ProcessCmd::Mask(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./masked");
+                if !args.dust && args.intervals.is_none() && args.homopolymer.is_none() {
+                    e_exit("ARGS", "Choose at least one masking method: --dust, --intervals or --homopolymer", 1);
+                }
+                let options = process::MaskOptions {
+                    dust: args.dust.then_some((args.dust_window, args.dust_level)),
+                    intervals: args.intervals.map(|path| {
+                        process::SeqMask::load_intervals(&path).unwrap_or_else(|e| e_exit("FILE-READ", &e, 1))
+                    }),
+                    homopolymer: args.homopolymer,
+                };
+                let style = if args.hard { process::MaskStyle::Hard } else { process::MaskStyle::Soft };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqMask::mask(files, out, &options, style, line_width);
+            }
+
+            ProcessCmd::Unmask(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./unmasked");
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                process::SeqMask::unmask(files, out, line_width);
+            }
+
+            ProcessCmd::Trim(args) => {
+                let files = args.input.get_files();
+                let out = args.output.get_file("./trimmed");
+                let adapters = match &args.adapters {
+                    Some(path) => utils::read_seq_records(path)
+                        .and_then(|records| records.map(|r| r.map(|r| r.seq)).collect::<Result<Vec<_>, _>>())
+                        .unwrap_or_else(|e| e_exit("FILE-READ", &format!("Failed to read adapters: {}", e), 1)),
+                    None => Vec::new(),
+                };
+                let options = process::TrimOptions {
+                    head: args.head,
+                    tail: args.tail,
+                    adapters: adapters.into_iter().filter(|a| !a.is_empty()).collect(),
+                    adapter_mismatches: args.adapter_mismatches,
+                    min_overlap: args.min_overlap,
+                    poly_g: args.poly_g,
+                    poly_a: args.poly_a,
+                    leading_qual: args.leading,
+                    trailing_qual: args.trailing,
+                    window: args.window.zip(args.window_qual),
+                    min_len: args.min_len,
+                };
+                println!("{}: {:?}", "Input files:".green().bold(), files);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                let stats = process::SeqTrim::trim(&files, out, &options, line_width);
+                info::InfoTrim::report(&files, &stats, args.report);
             }
         },
 
+        Commands::Validate(args) => {
+            let files = args.input.get_files();
+            let alphabet = args.alphabet.map(SeqType::alphabet);
+            eprintln!("{}: {:?}", "Input files:".green().bold(), files);
+            validate::Validator::validate(files, alphabet, args.reference, args.output);
+        }
+
         Commands::Extract(extract_cmd) => match extract_cmd {
             ExtractCmd::Segment(args) => {
                 let seq_files = args.input.get_files();
-                let out = args.output.get_file("./id_extracted_segment");
                 println!("{}: {:?}", "Input files:".green().bold(), seq_files);
                 
                 // Display position range if specified
@@ -311,23 +1409,41 @@ fn main() {
                              args.end.map_or("end".to_string(), |e| e.to_string()));
                 }
 
-                match (args.id_options.file, args.id_options.str) {
-                    (None, Some(id)) => {
-                        println!("{}: {:?}", "Input ID:".yellow().bold(), id);
-                        extract::ExtractSegment::extract_id(seq_files, id, out, args.start, args.end);
-                    },
-                    (Some(path), None) => {
-                        println!("{}: {:?}", "Input path:".yellow().bold(), path);
-                        extract::ExtractSegment::extract_id_files(seq_files, path, out, args.start, args.end);
-                    },
-                    _ => {}
-                };
+                if args.paired {
+                    let out = args.output.output.unwrap_or_else(|| PathBuf::from("./id_extracted_pairs"));
+                    println!("{}: {:?}", "Output directory:".green().bold(), out);
+                    match (args.id_options.file, args.id_options.str) {
+                        (None, Some(id)) => extract::ExtractSegment::extract_id_paired(seq_files, id, out, line_width),
+                        (Some(path), None) => extract::ExtractSegment::extract_id_files_paired(seq_files, path, out, line_width),
+                        _ => {}
+                    };
+                } else {
+                    let out = args.output.get_file("./id_extracted_segment");
+                    match (args.id_options.file, args.id_options.str, args.region) {
+                        (None, Some(id), None) => {
+                            println!("{}: {:?}", "Input ID:".yellow().bold(), id);
+                            extract::ExtractSegment::extract_id(seq_files, id, out, args.start, args.end, line_width);
+                        },
+                        (Some(path), None, None) => {
+                            println!("{}: {:?}", "Input path:".yellow().bold(), path);
+                            extract::ExtractSegment::extract_id_files(seq_files, path, out, args.start, args.end, line_width);
+                        },
+                        (None, None, Some(region)) => {
+                            println!("{}: {:?}", "Input region:".yellow().bold(), region);
+                            extract::ExtractSegment::extract_region(seq_files, region, out, line_width);
+                        },
+                        _ => {}
+                    };
+                }
             },
 
             ExtractCmd::Explain(args) => {
                 let seq_files = expand_file_paths(&args.seq_files);
                 let gff_files = expand_file_paths(&args.gff_files);
                 let out = args.output.get_file("./anno_extracted_segment");
+                if gff_files.is_empty() && seq_files.iter().any(|f| flatfile::FlatFormat::of_path(f).is_none()) {
+                    e_exit("ARGS", "Annotation files (--gff) are required unless all sequence files are GenBank/EMBL", 1);
+                }
                 
                 println!("{}: {:?}\n{}: {:?}",
                          "Input sequence files:".green().bold(), seq_files,
@@ -337,7 +1453,92 @@ fn main() {
                     println!("{}: {:?}", "Feature types filter:".yellow().bold(), types);
                 }
                 
-                extract::ExtractExplain::extract(seq_files, gff_files, out, args.feature_types.clone());
+                let format = match args.format {
+                    ExplainFormat::Fasta => extract::ExplainFormat::Fasta,
+                    ExplainFormat::Bed => extract::ExplainFormat::Bed,
+                };
+                extract::ExtractExplain::extract(seq_files, gff_files, out, args.feature_types.clone(), format, line_width);
+            }
+
+            ExtractCmd::Motif(args) => {
+                let seq_files = args.input.get_files();
+                let (format, default_out) = match args.format {
+                    MotifFormat::Bed => (extract::MotifFormat::Bed, "./motif_hits.bed"),
+                    MotifFormat::Gff3 => (extract::MotifFormat::Gff3, "./motif_hits.gff3"),
+                };
+                let out = args.output.get_file(default_out);
+                let kind = match args.kind {
+                    MotifKind::Exact => extract::MotifKind::Exact,
+                    MotifKind::Iupac => extract::MotifKind::Iupac,
+                    MotifKind::Regex => extract::MotifKind::Regex,
+                };
+
+                let patterns: Vec<(String, String)> = match (args.motif_options.file, args.motif_options.str) {
+                    (Some(path), _) => {
+                        let content = fs::read_to_string(&path).unwrap_or_else(|e| {
+                            e_exit("FILE-READ", &format!("Failed to read motif file {:?}: {}", path, e), 1)
+                        });
+                        content.lines()
+                            .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+                            .map(|l| match l.split_once('\t') {
+                                Some((name, pattern)) => (name.trim().to_string(), pattern.trim().to_string()),
+                                None => (l.trim().to_string(), l.trim().to_string()),
+                            })
+                            .collect()
+                    }
+                    (_, Some(pattern)) => vec![(pattern.clone(), pattern)],
+                    _ => unreachable!(),
+                };
+                let approximate = args.mismatches > 0 || args.indels;
+                let motifs: Vec<extract::Motif> = patterns.iter()
+                    .map(|(name, pattern)| {
+                        let motif = if approximate {
+                            extract::Motif::approximate(name, pattern, kind, args.mismatches, args.indels)
+                        } else {
+                            extract::Motif::new(name, pattern, kind)
+                        };
+                        motif.unwrap_or_else(|e| e_exit("ARGS", &e, 1))
+                    })
+                    .collect();
+
+                println!("{}: {:?}", "Input files:".green().bold(), seq_files);
+                println!("{}: {:?}", "Motifs:".yellow().bold(), patterns.iter().map(|(_, p)| p).collect::<Vec<_>>());
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                extract::ExtractMotif::extract(seq_files, &motifs, out, format, !args.forward, args.flank, line_width);
+            }
+
+            ExtractCmd::Pcr(args) => {
+                let seq_files = args.input.get_files();
+                let out = args.output.get_file("./amplicons.fa");
+                let primer = |name: &str, pattern: &str| {
+                    extract::Motif::approximate(name, pattern, extract::MotifKind::Iupac, args.mismatches, args.indels)
+                        .unwrap_or_else(|e| e_exit("ARGS", &e, 1))
+                };
+                let (forward, reverse) = (primer("forward", &args.forward), primer("reverse", &args.reverse));
+
+                println!("{}: {:?}", "Input files:".green().bold(), seq_files);
+                println!("{}: {} / {}", "Primers:".yellow().bold(), args.forward, args.reverse);
+                println!("{}: {:?}", "Output file:".green().bold(), out);
+                extract::ExtractPcr::amplify(seq_files, &forward, &reverse, args.max_size, out, args.bed, line_width);
+            }
+
+            ExtractCmd::Orf(args) => {
+                let seq_files = args.input.get_files();
+                let out = args.output.unwrap_or_else(|| PathBuf::from("./orfs"));
+                let options = extract::OrfOptions {
+                    min_len: args.min_len,
+                    code: utils::GeneticCode::new(args.code).unwrap_or_else(|e| e_exit("ARGS", &e, 1)),
+                    starts: match args.starts {
+                        OrfStarts::Atg => extract::StartCodons::Atg,
+                        OrfStarts::Alternative => extract::StartCodons::Alternative,
+                        OrfStarts::Any => extract::StartCodons::Any,
+                    },
+                    nested: matches!(args.nested, NestedOrfs::All),
+                };
+
+                println!("{}: {:?}", "Input files:".green().bold(), seq_files);
+                println!("{}: {:?}", "Output directory:".green().bold(), out);
+                extract::ExtractOrf::extract(seq_files, out, &options, line_width);
             }
         }
     }
@@ -1,9 +1,20 @@
 use super::error::{e_exit, e_println, ok_println};
+use crate::flatfile::{cds_phases, Feature, FlatFormat, FlatReader};
+use crate::utils::{complement_seq, is_mate_pair, line_wrap, mate_name, natural_cmp, read_annotations, read_bed, read_gff_directives, read_mate_pairs, read_seq_records, reverse_complement, Alphabet, AnnotationWriter, FileType, MultiFormatWriter, SeqRecord};
+use bio::bio_types::strand::Strand;
+use bio::io::gff::{GffType, Phase};
+use bio::io::{bed, fasta, fastq, gff};
 use memmap2::Mmap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
-use std::fs::File;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -72,7 +83,7 @@ impl ConvertCombine {
     /// * `output` - Shared output writer
     fn process_small(data: &Mmap, output: &Arc<Mutex<BufWriter<File>>>) -> std::io::Result<()> {
         let mut writer = output.lock()
-            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Failed to acquire lock"))?;
+            .map_err(|_| std::io::Error::other("Failed to acquire lock"))?;
 
         writer.write_all(data)?;
         Ok(())
@@ -102,7 +113,7 @@ impl ConvertCombine {
             // Acquire lock and write chunk
             {
                 let mut writer = output.lock()
-                    .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Failed to acquire lock"))?;
+                    .map_err(|_| std::io::Error::other("Failed to acquire lock"))?;
                 writer.write_all(chunk)?;
             }
 
@@ -130,4 +141,2631 @@ impl ConvertCombine {
 
         Ok(())
     }
+
+    /// Combines multiple files record by record
+    ///
+    /// Unlike [`ConvertCombine::combine_all`], every input is parsed with the reader matching
+    /// its format and written back through the matching writer, so missing trailing newlines
+    /// and CRLF line endings are fixed, and duplicated GFF directives are written only once.
+    /// Files are combined in the given order; the format of the first file decides the
+    /// output format and files of another format are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Vector of paths to input files
+    /// * `output` - Path to the output file
+    /// * `prefix_ids` - Prefix sequence IDs (and GFF seqnames/IDs) with the source file name
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn combine_records(paths: Vec<PathBuf>, output: PathBuf, prefix_ids: bool, line_width: usize) {
+        let Some(first) = paths.first() else {
+            e_exit("COMBINE", "No input files given", 1);
+        };
+
+        match FileType::infer_file_type(first) {
+            FileType::Fasta | FileType::Fastq => Self::combine_sequences(&paths, &output, prefix_ids, line_width),
+            FileType::Gff => Self::combine_annotations(&paths, &output, GffType::GFF3, prefix_ids),
+            FileType::Gtf => Self::combine_annotations(&paths, &output, GffType::GTF2, prefix_ids),
+            _ => e_exit("TYPE-ERROR", &format!("Unsupported format for record combine: {:?}", first), 1),
+        }
+
+        ok_println("MERGE_COMPLETE", "");
+    }
+
+    /// Combines FASTA or FASTQ files, following the format of the first file
+    fn combine_sequences(paths: &[PathBuf], output: &PathBuf, prefix_ids: bool, line_width: usize) {
+        let mut writer = match MultiFormatWriter::new(output, line_width) {
+            Ok(w) => w,
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        };
+        let is_fastq = matches!(FileType::infer_file_type(&paths[0]), FileType::Fastq);
+
+        for path in paths {
+            let prefix = prefix_ids.then(|| file_prefix(path));
+            let result = match (FileType::infer_file_type(path), is_fastq) {
+                (FileType::Fasta, false) => fasta::Reader::from_file(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|reader| {
+                        let mut count = 0;
+                        for record in reader.records() {
+                            let record = record.map_err(|e| format!("Failed to parse FASTA: {}", e))?;
+                            let id = prefixed_id(prefix.as_deref(), record.id());
+                            writer.fa.write(&id, record.desc(), record.seq())
+                                .map_err(|e| format!("Write failed: {}", e))?;
+                            count += 1;
+                        }
+                        Ok(count)
+                    }),
+                (FileType::Fastq, true) => fastq::Reader::from_file(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|reader| {
+                        let mut count = 0;
+                        for record in reader.records() {
+                            let record = record.map_err(|e| format!("Failed to parse FASTQ: {}", e))?;
+                            record.check().map_err(|e| format!("Invalid record {}: {}", record.id(), e))?;
+                            let id = prefixed_id(prefix.as_deref(), record.id());
+                            writer.fq.write(&id, record.desc(), record.seq(), record.qual())
+                                .map_err(|e| format!("Write failed: {}", e))?;
+                            count += 1;
+                        }
+                        Ok(count)
+                    }),
+                _ => Err("Format differs from the first input file, skipped".to_string()),
+            };
+
+            match result {
+                Ok(count) => ok_println("Merge", &format!("{} ({} records)", path.display(), count)),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+
+        if let Err(e) = writer.fa.flush().and_then(|_| writer.fq.flush()) {
+            e_println("WRITE_ERROR", &format!("Failed to flush output: {}", e));
+        }
+    }
+
+    /// Combines GFF3/GTF files, writing each directive once at the top of the output
+    fn combine_annotations(paths: &[PathBuf], output: &Path, gff_type: GffType, prefix_ids: bool) {
+        let mut writer = match AnnotationWriter::new(output, gff_type) {
+            Ok(w) => w,
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        };
+
+        // Only annotation files take part; other formats are reported and skipped
+        let inputs: Vec<(&PathBuf, GffType)> = paths.iter().filter_map(|path| {
+            match FileType::infer_file_type(path) {
+                FileType::Gff => Some((path, GffType::GFF3)),
+                FileType::Gtf => Some((path, GffType::GTF2)),
+                _ => {
+                    e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: not a GFF/GTF file, skipped", path.display()));
+                    None
+                }
+            }
+        }).collect();
+
+        // Collect the directives of all inputs first; the version line is written by the writer
+        let mut directives: Vec<String> = Vec::new();
+        for (path, _) in &inputs {
+            let prefix = prefix_ids.then(|| file_prefix(path));
+            for line in read_gff_directives(path) {
+                // Sequence regions follow the renamed seqnames
+                let line = match (line.strip_prefix("##sequence-region "), &prefix) {
+                    (Some(region), Some(prefix)) => format!("##sequence-region {}", prefixed_id(Some(prefix), region.trim_start())),
+                    _ => line,
+                };
+                if !directives.contains(&line) {
+                    directives.push(line);
+                }
+            }
+        }
+        for directive in &directives {
+            if let Err(e) = writer.write_directive(directive) {
+                e_exit("WRITE_ERROR", &format!("Failed to write directive: {}", e), 1);
+            }
+        }
+
+        for (path, input_type) in inputs {
+            let prefix = prefix_ids.then(|| file_prefix(path));
+            let result = read_annotations(path, input_type).and_then(|records| {
+                for mut record in records {
+                    if let Some(prefix) = &prefix {
+                        prefix_annotation(&mut record, prefix);
+                    }
+                    writer.write(&record)
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                }
+                Ok(())
+            });
+
+            match result {
+                Ok(_) => ok_println("Merge", &format!("{}", path.display())),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+
+        if let Err(e) = writer.flush() {
+            e_println("WRITE_ERROR", &format!("Failed to flush output: {}", e));
+        }
+    }
+}
+
+/// Name used to prefix the IDs coming from a file: the file name without extension
+fn file_prefix(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Joins an optional prefix and an ID as `prefix_id`
+fn prefixed_id(prefix: Option<&str>, id: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}_{}", prefix, id),
+        None => id.to_string(),
+    }
+}
+
+/// Prefixes the seqname and the identifier attributes of an annotation record,
+/// so `Parent` links keep pointing to the renamed `ID`s
+fn prefix_annotation(record: &mut gff::Record, prefix: &str) {
+    *record.seqname_mut() = prefixed_id(Some(prefix), record.seqname());
+    for (key, values) in record.attributes_mut().iter_all_mut() {
+        if matches!(key.as_str(), "ID" | "Parent" | "Derives_from" | "gene_id" | "transcript_id") {
+            for value in values.iter_mut() {
+                *value = prefixed_id(Some(prefix), value);
+            }
+        }
+    }
+}
+
+
+/// Output formats supported by [`ConvertFormat`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertTarget {
+    Fasta,
+    Fastq,
+    Gff3,
+    Gtf,
+    Bed,
+}
+
+/// Handles conversion between sequence and annotation formats
+pub struct ConvertFormat;
+
+impl ConvertFormat {
+
+    /// Converts the given files into a single output file of the target format
+    ///
+    /// Supported conversions:
+    /// - FASTQ → FASTA, and FASTA → FASTQ with a fixed quality value
+    /// - GenBank/EMBL → FASTA (sequences) or GFF3/GTF (feature table)
+    /// - GFF3 → GTF with `gene_id`/`transcript_id`, GTF → GFF3 with gene/transcript parents
+    /// - GFF3/GTF → BED12 (transcripts with exon blocks), BED → GFF3
+    ///
+    /// Files are converted in the given order; unsupported inputs are reported and skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Vector of paths to input files
+    /// * `output` - Path to the output file
+    /// * `target` - Format of the output file
+    /// * `quality` - Phred+33 quality character used when converting FASTA to FASTQ
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn convert(paths: Vec<PathBuf>, output: PathBuf, target: ConvertTarget, quality: u8, line_width: usize) {
+        match target {
+            ConvertTarget::Fasta | ConvertTarget::Fastq => {
+                let mut writer = match MultiFormatWriter::new(&output, line_width) {
+                    Ok(w) => w,
+                    Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+                };
+                Self::convert_each(&paths, |path| match (FileType::infer_file_type(path), target) {
+                    (FileType::Fasta, ConvertTarget::Fastq) => Self::fasta_to_fastq(path, &mut writer, quality),
+                    (FileType::Fastq, ConvertTarget::Fasta) => Self::fastq_to_fasta(path, &mut writer),
+                    (FileType::GenBank, ConvertTarget::Fasta) => Self::flat_to_fasta(path, FlatFormat::GenBank, &mut writer),
+                    (FileType::Embl, ConvertTarget::Fasta) => Self::flat_to_fasta(path, FlatFormat::Embl, &mut writer),
+                    _ => Err(format!("Conversion of this file to {:?} is not supported", target)),
+                });
+            }
+            ConvertTarget::Gff3 | ConvertTarget::Gtf => {
+                let gff_type = if target == ConvertTarget::Gtf { GffType::GTF2 } else { GffType::GFF3 };
+                let mut writer = match AnnotationWriter::new(&output, gff_type) {
+                    Ok(w) => w,
+                    Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+                };
+                Self::convert_each(&paths, |path| match (FileType::infer_file_type(path), target) {
+                    (FileType::GenBank, _) => Self::flat_to_gff(path, FlatFormat::GenBank, gff_type, &mut writer),
+                    (FileType::Embl, _) => Self::flat_to_gff(path, FlatFormat::Embl, gff_type, &mut writer),
+                    (FileType::Gff, ConvertTarget::Gtf) => Self::gff3_to_gtf(path, &mut writer),
+                    (FileType::Gtf, ConvertTarget::Gff3) => Self::gtf_to_gff3(path, &mut writer),
+                    (FileType::Bed, ConvertTarget::Gff3) => Self::bed_to_gff3(path, &mut writer),
+                    _ => Err(format!("Conversion of this file to {:?} is not supported", target)),
+                });
+                if let Err(e) = writer.flush() {
+                    e_println("WRITE_ERROR", &format!("Failed to flush output: {}", e));
+                }
+            }
+            ConvertTarget::Bed => {
+                let mut writer = match bed::Writer::to_file(&output) {
+                    Ok(w) => w,
+                    Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+                };
+                Self::convert_each(&paths, |path| match FileType::infer_file_type(path) {
+                    FileType::Gff => Self::gff_to_bed12(path, GffType::GFF3, &mut writer),
+                    FileType::Gtf => Self::gff_to_bed12(path, GffType::GTF2, &mut writer),
+                    _ => Err(format!("Conversion of this file to {:?} is not supported", target)),
+                });
+            }
+        }
+
+        ok_println("CONVERT_COMPLETE", "");
+    }
+
+    /// Runs the converter on every file in order and reports the result of each
+    fn convert_each<F>(paths: &[PathBuf], mut converter: F)
+    where
+        F: FnMut(&PathBuf) -> Result<usize, String>,
+    {
+        for path in paths {
+            match converter(path) {
+                Ok(count) => ok_println("Convert", &format!("{} ({} records)", path.display(), count)),
+                Err(e) => e_println("CONVERT_ERROR", &format!("Failed to convert file [{}]: {}", path.display(), e)),
+            }
+        }
+    }
+
+    /// Writes every FASTA record as FASTQ with a constant quality string
+    fn fasta_to_fastq(path: &PathBuf, writer: &mut MultiFormatWriter, quality: u8) -> Result<usize, String> {
+        let reader = fasta::Reader::from_file(path).map_err(|e| e.to_string())?;
+        let mut count = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Failed to parse FASTA: {}", e))?;
+            let qual = vec![quality; record.seq().len()];
+            writer.fq.write(record.id(), record.desc(), record.seq(), &qual)
+                .map_err(|e| format!("Write failed: {}", e))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Writes every FASTQ record as FASTA, dropping the qualities
+    fn fastq_to_fasta(path: &PathBuf, writer: &mut MultiFormatWriter) -> Result<usize, String> {
+        let reader = fastq::Reader::from_file(path).map_err(|e| e.to_string())?;
+        let mut count = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Failed to parse FASTQ: {}", e))?;
+            writer.fa.write(record.id(), record.desc(), record.seq())
+                .map_err(|e| format!("Write failed: {}", e))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Writes the sequences of a GenBank/EMBL file as FASTA
+    fn flat_to_fasta(path: &PathBuf, format: FlatFormat, writer: &mut MultiFormatWriter) -> Result<usize, String> {
+        let reader = FlatReader::from_file(path, format).map_err(|e| e.to_string())?;
+        let mut count = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Failed to parse {}: {}", format.name(), e))?;
+            writer.fa.write(&record.name, record.definition.as_deref(), &record.seq)
+                .map_err(|e| format!("Write failed: {}", e))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Writes the feature tables of a GenBank/EMBL file as GFF3/GTF
+    ///
+    /// GTF lines get the `gene_id`/`transcript_id` of [`ConvertFormat::gtf_ids`].
+    fn flat_to_gff(path: &PathBuf, format: FlatFormat, gff_type: GffType, writer: &mut AnnotationWriter) -> Result<usize, String> {
+        let reader = FlatReader::from_file(path, format).map_err(|e| e.to_string())?;
+        let mut count = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Failed to parse {}: {}", format.name(), e))?;
+            for (index, feature) in record.features.iter().enumerate() {
+                for mut gff_record in feature.to_gff(&record.name, index, format) {
+                    if gff_type == GffType::GTF2 {
+                        let (gene_id, transcript_id) = Self::gtf_ids(&record.name, feature, index);
+                        let attributes = gff_record.attributes_mut();
+                        attributes.remove("ID");
+                        attributes.insert("gene_id".to_string(), gene_id);
+                        if let Some(transcript_id) = transcript_id {
+                            attributes.insert("transcript_id".to_string(), transcript_id);
+                        }
+                    }
+                    writer.write(&gff_record)
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// GTF identifiers of a GenBank/EMBL feature
+    ///
+    /// `gene_id` is the `/locus_tag` or `/gene` qualifier, so a gene and its mRNA/CDS share it;
+    /// features with neither use `{seqname}_{kind}_{index + 1}`. Features other than `gene`
+    /// get the `/transcript_id` qualifier as `transcript_id`, or `{gene_id}.t1` without one.
+    fn gtf_ids(seqname: &str, feature: &Feature, index: usize) -> (String, Option<String>) {
+        let gene_id = feature.qualifier("locus_tag")
+            .or_else(|| feature.qualifier("gene"))
+            .map_or_else(|| format!("{}_{}_{}", seqname, feature.kind, index + 1), String::from);
+        let transcript_id = (feature.kind != "gene")
+            .then(|| feature.qualifier("transcript_id").map_or_else(|| format!("{}.t1", gene_id), String::from));
+        (gene_id, transcript_id)
+    }
+
+    /// Rewrites a GFF3 file as GTF, deriving `gene_id`/`transcript_id` from the `Parent` hierarchy
+    ///
+    /// Exon-level features (see [`EXON_LEVEL_TYPES`]) get their parent as `transcript_id` and its
+    /// parent as `gene_id`, one line per parent. Other features with a parent become `transcript`
+    /// lines (the GFF3 type is kept as `transcript_type`); top-level features keep their type
+    /// with their ID as `gene_id`. `Name` becomes `gene_name` or `transcript_name`.
+    fn gff3_to_gtf(path: &Path, writer: &mut AnnotationWriter) -> Result<usize, String> {
+        let records = read_annotations(path, GffType::GFF3)?;
+        let parent_of: HashMap<&str, &str> = records.iter()
+            .filter_map(|r| Some((r.attributes().get("ID")?.as_str(), r.attributes().get("Parent")?.as_str())))
+            .collect();
+        let gene_of = |transcript: &str| parent_of.get(transcript).copied().unwrap_or(transcript).to_string();
+
+        let mut count = 0;
+        for (n, record) in records.iter().enumerate() {
+            let id = record.attributes().get("ID").cloned()
+                .unwrap_or_else(|| format!("{}_{}", record.feature_type(), n + 1));
+            let parents: Vec<String> = record.attributes().get_vec("Parent").cloned().unwrap_or_default();
+
+            // (feature type, gene_id, transcript_id, name key) of each output line
+            let lines: Vec<(String, String, Option<String>, &str)> = if parents.is_empty() {
+                vec![(record.feature_type().to_string(), id, None, "gene_name")]
+            } else if EXON_LEVEL_TYPES.contains(&record.feature_type()) {
+                parents.iter()
+                    .map(|parent| (record.feature_type().to_string(), gene_of(parent), Some(parent.clone()), "transcript_name"))
+                    .collect()
+            } else {
+                vec![("transcript".to_string(), parents[0].clone(), Some(id), "transcript_name")]
+            };
+
+            for (feature_type, gene_id, transcript_id, name_key) in lines {
+                let mut gtf_record = record.clone();
+                let attributes = gtf_record.attributes_mut();
+                attributes.remove("ID");
+                attributes.remove("Parent");
+                if let Some(names) = attributes.remove("Name") {
+                    if feature_type == "gene" || feature_type == "transcript" {
+                        attributes.insert_many(name_key.to_string(), names);
+                    }
+                }
+                if feature_type == "transcript" && record.feature_type() != "transcript" {
+                    attributes.insert("transcript_type".to_string(), record.feature_type().to_string());
+                }
+                attributes.insert("gene_id".to_string(), gene_id);
+                if let Some(transcript_id) = transcript_id {
+                    attributes.insert("transcript_id".to_string(), transcript_id);
+                }
+                *gtf_record.feature_type_mut() = feature_type;
+
+                writer.write(&gtf_record)
+                    .map_err(|e| format!("Write failed: {}", e))?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Rewrites a GTF file as GFF3, reconstructing the gene and transcript parents
+    ///
+    /// `gene` and `transcript` lines are used when present and synthesized from the span of
+    /// their features otherwise; synthesized transcripts are `mRNA` when they have a CDS.
+    /// Each gene is written before its transcripts, each transcript before its features.
+    fn gtf_to_gff3(path: &Path, writer: &mut AnnotationWriter) -> Result<usize, String> {
+        let records = read_annotations(path, GffType::GTF2)?;
+
+        // Genes and transcripts in order of first appearance, indexed by ID
+        let mut genes: Vec<GeneModel> = Vec::new();
+        let mut gene_index: HashMap<String, usize> = HashMap::new();
+        let mut orphans = Vec::new();
+        for record in records {
+            let attribute = |key: &str| record.attributes().get(key).filter(|v| !v.is_empty()).cloned();
+            let Some(gene_id) = attribute("gene_id") else {
+                orphans.push(record);
+                continue;
+            };
+            let i = *gene_index.entry(gene_id.clone()).or_insert_with(|| {
+                genes.push(GeneModel {
+                    id: gene_id,
+                    record: None,
+                    transcripts: Vec::new(),
+                    transcript_index: HashMap::new(),
+                    features: Vec::new(),
+                });
+                genes.len() - 1
+            });
+            let gene = &mut genes[i];
+
+            match (record.feature_type(), attribute("transcript_id")) {
+                ("gene", _) => gene.record = Some(record),
+                (_, None) => gene.features.push(record),
+                (feature_type, Some(transcript_id)) => {
+                    let transcripts = &mut gene.transcripts;
+                    let i = *gene.transcript_index.entry(transcript_id.clone()).or_insert_with(|| {
+                        transcripts.push(TranscriptModel { id: transcript_id, record: None, features: Vec::new() });
+                        transcripts.len() - 1
+                    });
+                    let transcript = &mut transcripts[i];
+                    if feature_type == "transcript" {
+                        transcript.record = Some(record);
+                    } else {
+                        transcript.features.push(record);
+                    }
+                }
+            }
+        }
+
+        let mut count = 0;
+        let mut write = |record: &gff::Record| -> Result<(), String> {
+            count += 1;
+            writer.write(record).map_err(|e| format!("Write failed: {}", e))
+        };
+        for gene in &genes {
+            let gene_span: Vec<&gff::Record> = gene.transcripts.iter()
+                .flat_map(|t| t.record.iter().chain(&t.features))
+                .chain(&gene.features)
+                .collect();
+            let mut gene_record = Self::parent_record(gene.record.as_ref(), &gene_span, "gene");
+            Self::set_gff3_ids(&mut gene_record, &gene.id, None, "gene_name");
+            write(&gene_record)?;
+
+            for transcript in &gene.transcripts {
+                let has_cds = transcript.features.iter().any(|f| f.feature_type() == "CDS");
+                let feature_type = if has_cds { "mRNA" } else { "transcript" };
+                let features: Vec<&gff::Record> = transcript.features.iter().collect();
+                let mut transcript_record = Self::parent_record(transcript.record.as_ref(), &features, feature_type);
+                *transcript_record.feature_type_mut() = feature_type.to_string();
+                Self::set_gff3_ids(&mut transcript_record, &transcript.id, Some(&gene.id), "transcript_name");
+                write(&transcript_record)?;
+
+                let mut exon_numbers: HashMap<String, usize> = HashMap::new();
+                for feature in &transcript.features {
+                    let mut feature = feature.clone();
+                    // CDS parts share one ID, other features are numbered per type
+                    let id = match feature.feature_type() {
+                        "CDS" => format!("{}.cds", transcript.id),
+                        feature_type => {
+                            let n = exon_numbers.entry(feature_type.to_string()).or_insert(0);
+                            *n += 1;
+                            format!("{}.{}{}", transcript.id, feature_type, n)
+                        }
+                    };
+                    Self::set_gff3_ids(&mut feature, &id, Some(&transcript.id), "");
+                    write(&feature)?;
+                }
+            }
+            for feature in &gene.features {
+                let mut feature = feature.clone();
+                let attributes = feature.attributes_mut();
+                attributes.remove("gene_id");
+                attributes.remove("transcript_id");
+                attributes.insert("Parent".to_string(), gene.id.clone());
+                write(&feature)?;
+            }
+        }
+        for orphan in &orphans {
+            write(orphan)?;
+        }
+        Ok(count)
+    }
+
+    /// Returns the given parent line, or one spanning its features when the input has none
+    fn parent_record(record: Option<&gff::Record>, features: &[&gff::Record], feature_type: &str) -> gff::Record {
+        if let Some(record) = record {
+            return record.clone();
+        }
+        let mut parent = gff::Record::new();
+        if let Some(first) = features.first() {
+            *parent.seqname_mut() = first.seqname().to_string();
+            *parent.source_mut() = first.source().to_string();
+            *parent.strand_mut() = first.strand().map_or(".".to_string(), |s| s.strand_symbol().to_string());
+        }
+        *parent.feature_type_mut() = feature_type.to_string();
+        *parent.start_mut() = features.iter().map(|f| *f.start()).min().unwrap_or(1);
+        *parent.end_mut() = features.iter().map(|f| *f.end()).max().unwrap_or(1);
+        *parent.score_mut() = ".".to_string();
+        *parent.phase_mut() = Phase::from(None);
+        parent
+    }
+
+    /// Replaces the GTF identifiers of a record with GFF3 `ID`, `Parent` and `Name`
+    fn set_gff3_ids(record: &mut gff::Record, id: &str, parent: Option<&str>, name_key: &str) {
+        let attributes = record.attributes_mut();
+        attributes.remove("gene_id");
+        attributes.remove("transcript_id");
+        if let Some(names) = attributes.remove(name_key) {
+            attributes.insert_many("Name".to_string(), names);
+        }
+        attributes.insert("ID".to_string(), id.to_string());
+        if let Some(parent) = parent {
+            attributes.insert("Parent".to_string(), parent.to_string());
+        }
+    }
+
+    /// Writes the transcripts of a GFF3/GTF file as BED12
+    ///
+    /// Exons (or the CDS when a transcript has no exons) are grouped by `Parent` (GFF3) or
+    /// `transcript_id` (GTF) into blocks; the CDS span gives the thick part.
+    /// Exons or CDS parts starting at 0 are rejected, as GFF coordinates are 1-based.
+    fn gff_to_bed12(path: &Path, gff_type: GffType, writer: &mut bed::Writer<File>) -> Result<usize, String> {
+        let records = read_annotations(path, gff_type)?;
+        let key = if gff_type == GffType::GTF2 { "transcript_id" } else { "Parent" };
+
+        // Transcript ID, exons and CDS parts, in order of first appearance and indexed by ID
+        let mut transcripts: Vec<(String, Vec<&gff::Record>, Vec<&gff::Record>)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for record in &records {
+            let is_exon = record.feature_type() == "exon";
+            if !is_exon && record.feature_type() != "CDS" {
+                continue;
+            }
+            if *record.start() == 0 {
+                return Err(format!(
+                    "{} at {}:0-{} starts at 0, GFF coordinates are 1-based",
+                    record.feature_type(), record.seqname(), record.end()
+                ));
+            }
+            for transcript_id in record.attributes().get_vec(key).into_iter().flatten() {
+                let i = *index.entry(transcript_id.as_str()).or_insert_with(|| {
+                    transcripts.push((transcript_id.clone(), Vec::new(), Vec::new()));
+                    transcripts.len() - 1
+                });
+                if is_exon { transcripts[i].1.push(record) } else { transcripts[i].2.push(record) }
+            }
+        }
+
+        for (id, exons, cds) in &transcripts {
+            let parts = if exons.is_empty() { cds } else { exons };
+            let mut blocks: Vec<(u64, u64)> = parts.iter().map(|r| (r.start().saturating_sub(1), *r.end())).collect();
+            blocks.sort_unstable();
+            let (start, end) = (blocks[0].0, blocks.iter().map(|b| b.1).max().unwrap_or(0));
+            let (thick_start, thick_end) = match (cds.iter().map(|r| r.start().saturating_sub(1)).min(), cds.iter().map(|r| *r.end()).max()) {
+                (Some(thick_start), Some(thick_end)) => (thick_start, thick_end),
+                _ => (start, start),
+            };
+
+            let mut record = bed::Record::new();
+            record.set_chrom(parts[0].seqname());
+            record.set_start(start);
+            record.set_end(end);
+            record.set_name(id);
+            record.set_score("0");
+            record.push_aux(&parts[0].strand().map_or(".".to_string(), |s| s.strand_symbol().to_string()));
+            record.push_aux(&thick_start.to_string());
+            record.push_aux(&thick_end.to_string());
+            record.push_aux("0");
+            record.push_aux(&blocks.len().to_string());
+            record.push_aux(&blocks.iter().map(|(s, e)| format!("{},", e - s)).collect::<String>());
+            record.push_aux(&blocks.iter().map(|(s, _)| format!("{},", s - start)).collect::<String>());
+            writer.write(&record)
+                .map_err(|e| format!("Write failed: {}", e))?;
+        }
+        Ok(transcripts.len())
+    }
+
+    /// Writes BED intervals as GFF3
+    ///
+    /// BED12 lines become `mRNA` (or `transcript` without a thick part) with `exon` and
+    /// `CDS` children; shorter lines become `region` features named after the BED name.
+    fn bed_to_gff3(path: &Path, writer: &mut AnnotationWriter) -> Result<usize, String> {
+        let mut count = 0;
+        for (n, bed_record) in read_bed(path)?.iter().enumerate() {
+            let aux = |i: usize| bed_record.aux(i).filter(|v| !v.is_empty() && *v != ".");
+            let strand = match bed_record.strand() {
+                Some(Strand::Forward) => "+",
+                Some(Strand::Reverse) => "-",
+                _ => ".",
+            };
+            let feature = |feature_type: &str, start: u64, end: u64| {
+                let mut record = gff::Record::new();
+                *record.seqname_mut() = bed_record.chrom().to_string();
+                *record.source_mut() = "seq-here".to_string();
+                *record.feature_type_mut() = feature_type.to_string();
+                *record.start_mut() = start + 1;
+                *record.end_mut() = end;
+                *record.score_mut() = ".".to_string();
+                *record.strand_mut() = strand.to_string();
+                *record.phase_mut() = Phase::from(None);
+                record
+            };
+            let mut write = |record: &gff::Record| {
+                count += 1;
+                writer.write(record).map_err(|e| format!("Write failed: {}", e))
+            };
+
+            let blocks = Self::bed12_blocks(bed_record)
+                .map_err(|e| format!("BED line {}: {}", n + 1, e))?;
+            let Some((thick_start, thick_end, blocks)) = blocks else {
+                let mut record = feature("region", bed_record.start(), bed_record.end());
+                *record.score_mut() = aux(4).unwrap_or(".").to_string();
+                if let Some(name) = aux(3) {
+                    record.attributes_mut().insert("Name".to_string(), name.to_string());
+                }
+                write(&record)?;
+                continue;
+            };
+
+            let id = aux(3).map_or(format!("{}_{}", bed_record.chrom(), n + 1), String::from);
+            let coding = thick_start < thick_end;
+            let mut transcript = feature(if coding { "mRNA" } else { "transcript" }, bed_record.start(), bed_record.end());
+            transcript.attributes_mut().insert("ID".to_string(), id.clone());
+            write(&transcript)?;
+
+            for (i, &(start, end)) in blocks.iter().enumerate() {
+                let mut exon = feature("exon", start, end);
+                exon.attributes_mut().insert("ID".to_string(), format!("{}.exon{}", id, i + 1));
+                exon.attributes_mut().insert("Parent".to_string(), id.clone());
+                write(&exon)?;
+            }
+            if coding {
+                let cds: Vec<(u64, u64)> = blocks.iter()
+                    .map(|&(start, end)| (start.max(thick_start), end.min(thick_end)))
+                    .filter(|(start, end)| start < end)
+                    .collect();
+                let ranges: Vec<(u64, u64)> = cds.iter().map(|&(start, end)| (start + 1, end)).collect();
+                let phases = cds_phases(&ranges, strand == "-", 0);
+                for (&(start, end), phase) in cds.iter().zip(phases) {
+                    let mut part = feature("CDS", start, end);
+                    *part.phase_mut() = Phase::from(phase);
+                    part.attributes_mut().insert("ID".to_string(), format!("{}.cds", id));
+                    part.attributes_mut().insert("Parent".to_string(), id.clone());
+                    write(&part)?;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Thick span and absolute 0-based blocks of a BED12 record, `None` for shorter records
+    fn bed12_blocks(record: &bed::Record) -> Result<Option<Bed12Blocks>, String> {
+        let (Some(thick_start), Some(thick_end), Some(sizes), Some(starts)) =
+            (record.aux(6), record.aux(7), record.aux(10), record.aux(11)) else {
+            return Ok(None);
+        };
+        let numbers = |field: &str| -> Result<Vec<u64>, String> {
+            field.split(',')
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| v.trim().parse::<u64>().map_err(|_| format!("invalid BED12 field {}", field)))
+                .collect()
+        };
+        let parse = |field: &str| field.parse::<u64>().map_err(|_| format!("invalid BED12 field {}", field));
+        let (sizes, starts) = (numbers(sizes)?, numbers(starts)?);
+        if sizes.len() != starts.len() || sizes.is_empty() {
+            return Err("block sizes and starts differ in number".to_string());
+        }
+        let blocks = starts.iter().zip(&sizes)
+            .map(|(&start, &size)| (record.start() + start, record.start() + start + size))
+            .collect();
+        Ok(Some((parse(thick_start)?, parse(thick_end)?, blocks)))
+    }
+
+}
+
+/// Feature types placed below transcripts when converting GFF3 to GTF
+const EXON_LEVEL_TYPES: [&str; 9] = [
+    "exon", "CDS", "five_prime_UTR", "three_prime_UTR", "UTR", "start_codon", "stop_codon", "intron", "Selenocysteine",
+];
+
+/// Thick start, thick end and absolute 0-based blocks of a BED12 line
+type Bed12Blocks = (u64, u64, Vec<(u64, u64)>);
+
+/// A GTF gene being rebuilt as GFF3
+struct GeneModel {
+    id: String,
+    record: Option<gff::Record>,       // `gene` line, if the input has one
+    transcripts: Vec<TranscriptModel>,
+    transcript_index: HashMap<String, usize>, // Position of each transcript ID in `transcripts`
+    features: Vec<gff::Record>,        // Gene-level features without a transcript_id
+}
+
+/// A GTF transcript being rebuilt as GFF3
+struct TranscriptModel {
+    id: String,
+    record: Option<gff::Record>,       // `transcript` line, if the input has one
+    features: Vec<gff::Record>,
+}
+
+/// Streams the records of every FASTA/FASTQ input through `op` into one output file
+///
+/// Inputs are processed in the given order; records for which `op` returns `None` are dropped.
+///
+/// # Arguments
+///
+/// * `paths` - Input FASTA/FASTQ files
+/// * `output` - Path to the output file
+/// * `tip` - Label used when reporting progress
+/// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+/// * `op` - Per-record operation, receiving the source file and the record
+///
+/// # Returns
+///
+/// * `usize` - Number of records written
+fn transform_records<F>(paths: &[PathBuf], output: &PathBuf, tip: &str, line_width: usize, mut op: F) -> usize
+where
+    F: FnMut(&Path, SeqRecord) -> Option<SeqRecord>,
+{
+    let mut writer = match MultiFormatWriter::new(output, line_width) {
+        Ok(w) => w,
+        Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+    };
+
+    let mut total = 0;
+    for path in paths {
+        let result = read_seq_records(path).and_then(|records| {
+            let mut count = 0;
+            for record in records {
+                if let Some(record) = op(path, record?) {
+                    writer.write_record(&record)
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                    count += 1;
+                }
+            }
+            // Keep the output in input order even when FASTA and FASTQ inputs are mixed
+            writer.flush().map_err(|e| format!("Write failed: {}", e))?;
+            Ok(count)
+        });
+
+        match result {
+            Ok(count) => {
+                total += count;
+                ok_println(tip, &format!("{} ({} records)", path.display(), count));
+            }
+            Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+        }
+    }
+    total
+}
+
+
+/// Sequence operations of [`RevComp`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevCompMode {
+    ReverseComplement,
+    Complement,
+    Reverse,
+}
+
+/// Reverses and/or complements nucleotide sequences
+pub struct RevComp;
+
+impl RevComp {
+
+    /// Applies the operation to every record of the given FASTA/FASTQ files
+    ///
+    /// IUPAC ambiguity codes are complemented, RNA stays RNA and case is preserved.
+    /// FASTQ qualities are reversed together with the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `mode` - Reverse-complement, complement or reverse
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn revcomp(paths: Vec<PathBuf>, output: PathBuf, mode: RevCompMode, line_width: usize) {
+        transform_records(&paths, &output, "RevComp", line_width, |_, mut record| {
+            Self::apply(&mut record, mode);
+            Some(record)
+        });
+        ok_println("REVCOMP_COMPLETE", "");
+    }
+
+    /// Applies the operation to a single record in place
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The record to modify
+    /// * `mode` - Reverse-complement, complement or reverse
+    pub fn apply(record: &mut SeqRecord, mode: RevCompMode) {
+        match mode {
+            RevCompMode::ReverseComplement => record.seq = reverse_complement(&record.seq),
+            RevCompMode::Complement => record.seq = complement_seq(&record.seq),
+            RevCompMode::Reverse => record.seq.reverse(),
+        }
+
+        if mode != RevCompMode::Complement {
+            if let Some(qual) = record.qual.as_mut() {
+                qual.reverse();
+            }
+        }
+    }
+}
+
+
+/// Criteria used by [`SeqFilter`], `None` disables a criterion
+///
+/// A record is kept only if it passes every enabled criterion.
+#[derive(Debug, Clone, Default)]
+pub struct FilterOptions {
+    pub min_len: Option<usize>,      // Minimum sequence length
+    pub max_len: Option<usize>,      // Maximum sequence length
+    pub min_gc: Option<f32>,         // Minimum GC content (0.0 - 1.0)
+    pub max_gc: Option<f32>,         // Maximum GC content (0.0 - 1.0)
+    pub max_n_frac: Option<f32>,     // Maximum fraction of N bases (0.0 - 1.0)
+    pub min_mean_qual: Option<f64>,  // Minimum mean Phred quality, FASTQ only
+    pub seq_type: Option<Alphabet>,  // Required sequence alphabet, see `Alphabet::detect`
+}
+
+/// Filters FASTA/FASTQ records by length, composition, quality and sequence type
+pub struct SeqFilter;
+
+impl SeqFilter {
+
+    /// Writes the records passing all criteria to the output file
+    ///
+    /// Records are streamed one by one. Each removed record is counted against the
+    /// first criterion it fails (in the order of [`FilterOptions`]), and the counts are
+    /// reported when done.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `options` - Filter criteria
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn filter(paths: Vec<PathBuf>, output: PathBuf, options: &FilterOptions, line_width: usize) {
+        let mut removed: Vec<(&str, usize)> = vec![
+            ("min-len", 0), ("max-len", 0), ("min-gc", 0), ("max-gc", 0),
+            ("max-n-frac", 0), ("min-mean-qual", 0), ("seq-type", 0),
+        ];
+
+        let kept = transform_records(&paths, &output, "Filter", line_width, |_, record| {
+            match Self::failed_criterion(&record, options) {
+                Some(index) => {
+                    removed[index].1 += 1;
+                    None
+                }
+                None => Some(record),
+            }
+        });
+
+        let total_removed: usize = removed.iter().map(|(_, n)| n).sum();
+        ok_println("Kept", &format!("{} records", kept));
+        ok_println("Removed", &format!("{} records", total_removed));
+        for (criterion, count) in removed.iter().filter(|(_, n)| *n > 0) {
+            ok_println(criterion, &format!("{} records removed", count));
+        }
+        ok_println("FILTER_COMPLETE", "");
+    }
+
+    /// Returns the index of the first criterion the record fails, or `None` if it passes
+    fn failed_criterion(record: &SeqRecord, options: &FilterOptions) -> Option<usize> {
+        let len = record.seq.len();
+
+        if options.min_len.is_some_and(|min| len < min) {
+            return Some(0);
+        }
+        if options.max_len.is_some_and(|max| len > max) {
+            return Some(1);
+        }
+        if options.min_gc.is_some() || options.max_gc.is_some() {
+            let gc = bio::seq_analysis::gc::gc_content(&record.seq);
+            if options.min_gc.is_some_and(|min| gc < min) {
+                return Some(2);
+            }
+            if options.max_gc.is_some_and(|max| gc > max) {
+                return Some(3);
+            }
+        }
+        if let Some(max) = options.max_n_frac {
+            let n_count = record.seq.iter().filter(|&&c| c == b'N' || c == b'n').count();
+            if len > 0 && n_count as f32 / len as f32 > max {
+                return Some(4);
+            }
+        }
+        if let (Some(min), Some(qual)) = (options.min_mean_qual, &record.qual) {
+            if mean_quality(qual) < min {
+                return Some(5);
+            }
+        }
+        if let Some(seq_type) = options.seq_type {
+            if Alphabet::detect(&record.seq).map(|(alphabet, _)| alphabet) != Some(seq_type) {
+                return Some(6);
+            }
+        }
+        None
+    }
+}
+
+/// Mean Phred quality of a Phred+33 encoded quality string
+fn mean_quality(qual: &[u8]) -> f64 {
+    if qual.is_empty() {
+        return 0.0;
+    }
+    qual.iter().map(|&q| q.saturating_sub(33) as f64).sum::<f64>() / qual.len() as f64
+}
+
+
+/// What two records must share to be considered duplicates by [`SeqDedup`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupKey {
+    Id,        // Same record ID
+    Sequence,  // Same sequence (case-insensitive)
+    Canonical, // Same sequence or reverse complement (case-insensitive)
+}
+
+/// Removes duplicate FASTA/FASTQ records
+pub struct SeqDedup;
+
+impl SeqDedup {
+
+    /// Writes the first occurrence of every record to the output file
+    ///
+    /// Only a 128-bit hash of each key is kept in memory, not the sequences themselves.
+    /// Memory is not bounded: it grows by one 16-byte hash per distinct record (up to about
+    /// twice that with hash table overhead), plus the kept ID when `map_output` is given.
+    /// When `map_output` is given, a TSV of `kept_id<TAB>removed_id` lines is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `key` - Duplicate criterion
+    /// * `map_output` - Optional path of the kept → removed ID mapping
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn dedup(paths: Vec<PathBuf>, output: PathBuf, key: DedupKey, map_output: Option<PathBuf>, line_width: usize) {
+        let mut map_writer = map_output.map(|path| match File::create(&path) {
+            Ok(f) => BufWriter::new(f),
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        });
+
+        // Kept IDs are only stored when the mapping is requested
+        let mut seen: HashSet<u128> = HashSet::new();
+        let mut kept_ids: HashMap<u128, String> = HashMap::new();
+        let mut removed = 0;
+
+        let kept = transform_records(&paths, &output, "Dedup", line_width, |_, record| {
+            let hash = Self::key_hash(&record, key);
+            let duplicate = match map_writer.as_mut() {
+                Some(writer) => match kept_ids.get(&hash) {
+                    Some(kept_id) => {
+                        if let Err(e) = writeln!(writer, "{}\t{}", kept_id, record.id) {
+                            e_exit("WRITE_ERROR", &format!("Failed to write mapping: {}", e), 1);
+                        }
+                        true
+                    }
+                    None => {
+                        kept_ids.insert(hash, record.id.clone());
+                        false
+                    }
+                },
+                None => !seen.insert(hash),
+            };
+            if duplicate {
+                removed += 1;
+                None
+            } else {
+                Some(record)
+            }
+        });
+
+        if let Some(Err(e)) = map_writer.as_mut().map(|w| w.flush()) {
+            e_println("WRITE_ERROR", &format!("Failed to flush mapping: {}", e));
+        }
+        ok_println("Kept", &format!("{} records", kept));
+        ok_println("Removed", &format!("{} duplicates", removed));
+        ok_println("DEDUP_COMPLETE", "");
+    }
+
+    /// Hashes the part of the record selected by `key`
+    fn key_hash(record: &SeqRecord, key: DedupKey) -> u128 {
+        match key {
+            DedupKey::Id => hash128(record.id.as_bytes()),
+            DedupKey::Sequence => hash128(&record.seq.to_ascii_uppercase()),
+            DedupKey::Canonical => {
+                let forward = record.seq.to_ascii_uppercase();
+                let reverse = reverse_complement(&forward);
+                hash128(std::cmp::min(&forward, &reverse))
+            }
+        }
+    }
+}
+
+/// 128-bit hash built from two differently seeded 64-bit hashes
+fn hash128(data: &[u8]) -> u128 {
+    let half = |seed: u8| {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        data.hash(&mut hasher);
+        hasher.finish() as u128
+    };
+    (half(0) << 64) | half(1)
+}
+
+
+/// Record order used by [`SeqSort`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    Name,               // Lexicographic ID order
+    Natural,            // Natural ID order, `chr2` before `chr10`
+    Length,             // Sequence length
+    Order(Vec<String>), // User-given ID order, unlisted IDs go last (also when reversed)
+}
+
+/// Sorts sequence records or annotations
+pub struct SeqSort;
+
+impl SeqSort {
+
+    /// Sorts all records of the input files into one output file
+    ///
+    /// FASTA/FASTQ records are ordered by `key`; ties keep their input order.
+    /// When the records exceed `max_memory` bytes, sorted chunks are written next to the
+    /// output file and merged afterwards (external merge sort).
+    ///
+    /// If the first input is a GFF3/GTF file, annotations are sorted instead: by seqname in
+    /// natural order, then by start, with every child feature placed after its parent.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input files, either all FASTA/FASTQ or all GFF3/GTF
+    /// * `output` - Path to the output file
+    /// * `key` - Record order for sequence files
+    /// * `reverse` - Reverse the record order for sequence files
+    /// * `max_memory` - Approximate memory budget in bytes for sequence files
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn sort(paths: Vec<PathBuf>, output: PathBuf, key: SortKey, reverse: bool, max_memory: usize, line_width: usize) {
+        let Some(first) = paths.first() else {
+            e_exit("SORT", "No input files given", 1);
+        };
+
+        match FileType::infer_file_type(first) {
+            FileType::Fasta | FileType::Fastq => Self::sort_sequences(&paths, &output, &key, reverse, max_memory, line_width),
+            FileType::Gff => Self::sort_annotations(&paths, &output, GffType::GFF3),
+            FileType::Gtf => Self::sort_annotations(&paths, &output, GffType::GTF2),
+            _ => e_exit("TYPE-ERROR", &format!("Unsupported format for sort: {:?}", first), 1),
+        }
+
+        ok_println("SORT_COMPLETE", "");
+    }
+
+    /// Sorts FASTA/FASTQ records, spilling to disk when over the memory budget
+    fn sort_sequences(paths: &[PathBuf], output: &PathBuf, key: &SortKey, reverse: bool, max_memory: usize, line_width: usize) {
+        let ranks: HashMap<&str, usize> = match key {
+            SortKey::Order(ids) => ids.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect(),
+            _ => HashMap::new(),
+        };
+        let compare = |a: &SeqRecord, b: &SeqRecord| {
+            let ord = match key {
+                SortKey::Name => a.id.cmp(&b.id),
+                SortKey::Natural => natural_cmp(&a.id, &b.id),
+                SortKey::Length => a.seq.len().cmp(&b.seq.len()),
+                SortKey::Order(_) => {
+                    // Only the listed IDs are reversed, unlisted IDs stay last
+                    let rank = |r: &SeqRecord| ranks.get(r.id.as_str()).copied();
+                    return match (rank(a), rank(b)) {
+                        (Some(a), Some(b)) if reverse => b.cmp(&a),
+                        (Some(a), Some(b)) => a.cmp(&b),
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    };
+                }
+            };
+            if reverse { ord.reverse() } else { ord }
+        };
+
+        // All inputs must share the format of the first file, so chunks can be read back
+        let extension = first_extension(&paths[0]);
+        let first_type = FileType::infer_file_type(&paths[0]);
+        let tmp_dir = output.with_file_name(format!(
+            ".{}.sort_tmp",
+            output.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+        ));
+
+        let mut chunk: Vec<SeqRecord> = Vec::new();
+        let mut chunk_bytes = 0;
+        let mut chunk_files: Vec<PathBuf> = Vec::new();
+
+        for path in paths {
+            if FileType::infer_file_type(path) != first_type {
+                e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: format differs from the first input file, skipped", path.display()));
+                continue;
+            }
+            let result = read_seq_records(path).and_then(|records| {
+                let mut count = 0;
+                for record in records {
+                    let record = record?;
+                    chunk_bytes += record.id.len() + record.seq.len() * 2 + 64;
+                    chunk.push(record);
+                    count += 1;
+
+                    if chunk_bytes >= max_memory {
+                        chunk.sort_by(compare);
+                        let chunk_path = tmp_dir.join(format!("chunk_{}.{}", chunk_files.len(), extension));
+                        Self::write_chunk(&chunk_path, &chunk, line_width)?;
+                        chunk_files.push(chunk_path);
+                        chunk.clear();
+                        chunk_bytes = 0;
+                    }
+                }
+                Ok(count)
+            });
+
+            match result {
+                Ok(count) => ok_println("Sort", &format!("{} ({} records)", path.display(), count)),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+
+        chunk.sort_by(compare);
+        let result = if chunk_files.is_empty() {
+            Self::write_chunk(output, &chunk, line_width)
+        } else {
+            ok_println("Merge", &format!("{} sorted chunks", chunk_files.len() + 1));
+            let chunk_path = tmp_dir.join(format!("chunk_{}.{}", chunk_files.len(), extension));
+            Self::write_chunk(&chunk_path, &chunk, line_width).and_then(|_| {
+                chunk_files.push(chunk_path);
+                drop(chunk);
+                Self::merge_chunks(&chunk_files, output, compare, line_width)
+            })
+        };
+
+        if tmp_dir.exists() {
+            let _ = fs::remove_dir_all(&tmp_dir);
+        }
+        if let Err(e) = result {
+            e_exit("WRITE_ERROR", &format!("Failed to write sorted output: {}", e), 1);
+        }
+    }
+
+    /// Writes a sorted chunk of records to a file
+    fn write_chunk(path: &PathBuf, records: &[SeqRecord], line_width: usize) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let mut writer = MultiFormatWriter::new(path, line_width).map_err(|e| format!("Failed to create file: {}", e))?;
+        for record in records {
+            writer.write_record(record).map_err(|e| format!("Write failed: {}", e))?;
+        }
+        writer.flush().map_err(|e| format!("Write failed: {}", e))
+    }
+
+    /// Merges sorted chunk files into the output, taking the earliest chunk on ties
+    fn merge_chunks<F>(chunk_files: &[PathBuf], output: &PathBuf, compare: F, line_width: usize) -> Result<(), String>
+    where
+        F: Fn(&SeqRecord, &SeqRecord) -> Ordering,
+    {
+        let mut readers = chunk_files.iter()
+            .map(|path| read_seq_records(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut heads = readers.iter_mut()
+            .map(|reader| reader.next().transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut writer = MultiFormatWriter::new(output, line_width).map_err(|e| format!("Failed to create file: {}", e))?;
+        loop {
+            let mut best: Option<usize> = None;
+            for (i, head) in heads.iter().enumerate() {
+                if let Some(record) = head {
+                    if best.is_none_or(|b| compare(record, heads[b].as_ref().unwrap()) == Ordering::Less) {
+                        best = Some(i);
+                    }
+                }
+            }
+            let Some(best) = best else { break };
+
+            if let Some(record) = heads[best].take() {
+                writer.write_record(&record).map_err(|e| format!("Write failed: {}", e))?;
+            }
+            heads[best] = readers[best].next().transpose()?;
+        }
+        writer.flush().map_err(|e| format!("Write failed: {}", e))
+    }
+
+    /// Sorts annotations by seqname and start, keeping children after their parents
+    fn sort_annotations(paths: &[PathBuf], output: &Path, gff_type: GffType) {
+        let mut writer = match AnnotationWriter::new(output, gff_type) {
+            Ok(w) => w,
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        };
+
+        let mut directives: Vec<String> = Vec::new();
+        let mut records: Vec<gff::Record> = Vec::new();
+        for path in paths {
+            let input_type = match FileType::infer_file_type(path) {
+                FileType::Gff => GffType::GFF3,
+                FileType::Gtf => GffType::GTF2,
+                _ => {
+                    e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: not a GFF/GTF file, skipped", path.display()));
+                    continue;
+                }
+            };
+            for directive in read_gff_directives(path) {
+                if !directives.contains(&directive) {
+                    directives.push(directive);
+                }
+            }
+            match read_annotations(path, input_type) {
+                Ok(mut file_records) => {
+                    ok_println("Sort", &format!("{} ({} records)", path.display(), file_records.len()));
+                    records.append(&mut file_records);
+                }
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+
+        let result = directives.iter()
+            .try_for_each(|directive| writer.write_directive(directive))
+            .and_then(|_| {
+                Self::parent_first_order(&records)
+                    .into_iter()
+                    .try_for_each(|i| writer.write(&records[i]))
+            })
+            .and_then(|_| writer.flush());
+        if let Err(e) = result {
+            e_exit("WRITE_ERROR", &format!("Failed to write sorted output: {}", e), 1);
+        }
+    }
+
+    /// Orders annotation records so top-level features are sorted by seqname and position,
+    /// each followed by its descendants (through `Parent`), also sorted by position
+    fn parent_first_order(records: &[gff::Record]) -> Vec<usize> {
+        let mut id_index: HashMap<&str, usize> = HashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            if let Some(id) = record.attributes().get("ID") {
+                id_index.entry(id.as_str()).or_insert(i);
+            }
+        }
+
+        // Features whose parent is missing are treated as top-level features
+        let mut roots: Vec<usize> = Vec::new();
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            match record.attributes().get("Parent").and_then(|p| id_index.get(p.as_str())) {
+                Some(&parent) if parent != i => children.entry(parent).or_default().push(i),
+                _ => roots.push(i),
+            }
+        }
+
+        let position = |&a: &usize, &b: &usize| {
+            let (a, b) = (&records[a], &records[b]);
+            natural_cmp(a.seqname(), b.seqname())
+                .then(a.start().cmp(b.start()))
+                .then(b.end().cmp(a.end()))
+        };
+        roots.sort_by(position);
+        for list in children.values_mut() {
+            list.sort_by(position);
+        }
+
+        // Depth-first walk; `visited` guards against Parent cycles
+        let mut order = Vec::with_capacity(records.len());
+        let mut visited = vec![false; records.len()];
+        let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+        while let Some(i) = stack.pop() {
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+            order.push(i);
+            if let Some(list) = children.get(&i) {
+                stack.extend(list.iter().rev());
+            }
+        }
+        // Records only reachable through a cycle are appended in input order
+        order.extend((0..records.len()).filter(|&i| !visited[i]));
+        order
+    }
+}
+
+/// How [`SeqSplit`] distributes records over the output files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMode {
+    Parts(usize),   // N output files, records dealt out round-robin
+    Records(usize), // At most N records per output file
+    Bytes(u64),     // At most N bytes per output file (a larger record gets its own file)
+    ById,           // One output file per record
+}
+
+/// Default output name template of [`SeqSplit`]
+pub const SPLIT_TEMPLATE: &str = "{stem}.part_{num}.{ext}";
+/// Default output name template of [`SeqSplit`] with [`SplitMode::ById`]
+pub const SPLIT_TEMPLATE_BY_ID: &str = "{id}.{ext}";
+
+/// Splits sequence files into smaller files, the counterpart of [`ConvertCombine`]
+pub struct SeqSplit;
+
+impl SeqSplit {
+
+    /// Splits every input FASTA/FASTQ file, reading each input only once
+    ///
+    /// Output names are rendered from `template`, which may contain
+    /// `{stem}` (input file name without extension), `{ext}` (input extension),
+    /// `{num}` (1-based part number, zero-padded to 3 digits) and `{id}` (record ID, with `--by-id`).
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files, each split separately
+    /// * `out_dir` - Directory receiving the parts, created if missing
+    /// * `mode` - How records are distributed
+    /// * `template` - Output name template, see [`SPLIT_TEMPLATE`] and [`SPLIT_TEMPLATE_BY_ID`]
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn split(paths: Vec<PathBuf>, out_dir: PathBuf, mode: SplitMode, template: &str, line_width: usize) {
+        if let Err(e) = fs::create_dir_all(&out_dir) {
+            e_exit("FILE_CREATE", &format!("Failed to create directory {}: {}", out_dir.display(), e), 1);
+        }
+
+        // Output names already used in this run, across all inputs
+        let mut used: HashSet<PathBuf> = HashSet::new();
+        for path in &paths {
+            match Self::split_file(path, &out_dir, mode, template, &mut used, line_width) {
+                Ok((records, files)) => ok_println(
+                    "Split",
+                    &format!("{} ({} records into {} files)", path.display(), records, files),
+                ),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+
+        ok_println("SPLIT_COMPLETE", "");
+    }
+
+    /// Splits one input file, returning the number of records and written files
+    fn split_file(
+        path: &Path,
+        out_dir: &Path,
+        mode: SplitMode,
+        template: &str,
+        used: &mut HashSet<PathBuf>,
+        line_width: usize,
+    ) -> Result<(usize, usize), String> {
+        let stem = file_prefix(path);
+        let ext = first_extension(path);
+        let mut id_counts: HashMap<String, usize> = HashMap::new();
+
+        let mut open_part = |n: usize, id: &str| -> Result<MultiFormatWriter, String> {
+            // Repeated IDs get a `_2`, `_3`, ... suffix so every record keeps its own file
+            let seen = id_counts.entry(id.to_string()).or_insert(0);
+            *seen += 1;
+            let id = match *seen {
+                1 => safe_file_name(id),
+                k => format!("{}_{}", safe_file_name(id), k),
+            };
+            let name = template
+                .replace("{stem}", &stem)
+                .replace("{ext}", &ext)
+                .replace("{num}", &format!("{:03}", n))
+                .replace("{id}", &id);
+            let part = out_dir.join(name);
+            if !used.insert(part.clone()) {
+                return Err(format!(
+                    "Output file {} would be written twice, add {{num}}, {{id}} or {{stem}} to the name template",
+                    part.display()
+                ));
+            }
+            MultiFormatWriter::new(&part, line_width).map_err(|e| format!("Failed to create file {}: {}", part.display(), e))
+        };
+
+        let mut parts: Vec<MultiFormatWriter> = Vec::new();
+        let mut files = 0;
+        let mut part_records = 0;
+        let mut part_bytes = 0;
+        let mut count = 0;
+
+        for record in read_seq_records(path)? {
+            let record = record?;
+            let index = match mode {
+                SplitMode::Parts(n) => {
+                    let index = count % n.max(1);
+                    if index == parts.len() {
+                        parts.push(open_part(index + 1, &record.id)?);
+                        files += 1;
+                    }
+                    index
+                }
+                _ => {
+                    let size = record_size(&record, line_width);
+                    let new_part = match mode {
+                        SplitMode::Records(n) => part_records >= n,
+                        SplitMode::Bytes(n) => part_bytes > 0 && part_bytes + size > n,
+                        _ => true,
+                    };
+                    if parts.is_empty() || new_part {
+                        // Only the current part stays open
+                        if let Some(mut previous) = parts.pop() {
+                            previous.flush().map_err(|e| format!("Write failed: {}", e))?;
+                        }
+                        parts.push(open_part(files + 1, &record.id)?);
+                        files += 1;
+                        part_records = 0;
+                        part_bytes = 0;
+                    }
+                    part_records += 1;
+                    part_bytes += size;
+                    0
+                }
+            };
+
+            parts[index].write_record(&record).map_err(|e| format!("Write failed: {}", e))?;
+            count += 1;
+        }
+
+        for part in parts.iter_mut() {
+            part.flush().map_err(|e| format!("Write failed: {}", e))?;
+        }
+        Ok((count, files))
+    }
+}
+
+/// Number of records picked by [`SeqSample`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    Count(usize),  // Exactly N records (or all of them if fewer), by reservoir sampling
+    Fraction(f64), // Each record is kept with this probability
+}
+
+/// Random subsampling of sequence records
+pub struct SeqSample;
+
+impl SeqSample {
+
+    /// Samples records from the FASTA/FASTQ inputs, taken together as one stream
+    ///
+    /// Sampled records keep their input order. Inputs must share the format of the first file.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `size` - Fixed count or fraction of records to keep
+    /// * `seed` - Seed of the random generator, a random seed is used if `None`
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn sample(paths: Vec<PathBuf>, output: PathBuf, size: SampleSize, seed: Option<u64>, line_width: usize) {
+        let Some(first) = paths.first() else {
+            e_exit("SAMPLE", "No input files given", 1);
+        };
+        let first_type = FileType::infer_file_type(first);
+
+        let records = paths.iter()
+            .filter(|path| {
+                let same = FileType::infer_file_type(path) == first_type;
+                if !same {
+                    e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: format differs from the first input file, skipped", path.display()));
+                }
+                same
+            })
+            .filter_map(|path| match read_seq_records(path) {
+                Ok(records) => Some(records),
+                Err(e) => {
+                    e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e));
+                    None
+                }
+            })
+            .flatten();
+
+        let mut writer = match MultiFormatWriter::new(&output, line_width) {
+            Ok(w) => w,
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        };
+        let result = sample_stream(records, size, seed, |record| {
+            writer.write_record(record).map_err(|e| format!("Write failed: {}", e))
+        }).and_then(|counts| {
+            writer.flush().map_err(|e| format!("Write failed: {}", e))?;
+            Ok(counts)
+        });
+
+        match result {
+            Ok((seen, kept)) => ok_println("Sample", &format!("{} of {} records", kept, seen)),
+            Err(e) => e_exit("PROCESS_ERROR", &format!("Failed to sample records: {}", e), 1),
+        }
+        ok_println("SAMPLE_COMPLETE", "");
+    }
+
+    /// Samples the same read pairs from both mates of a paired-end dataset
+    ///
+    /// Both files are read in lockstep, so mates must be in the same order.
+    /// Each mate is written to `out_dir` under its input file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `r1`, `r2` - FASTA/FASTQ files of the first and second mates
+    /// * `out_dir` - Directory receiving the sampled mates, created if missing
+    /// * `size` - Fixed count or fraction of pairs to keep
+    /// * `seed` - Seed of the random generator, a random seed is used if `None`
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn sample_paired(r1: &Path, r2: &Path, out_dir: &Path, size: SampleSize, seed: Option<u64>, line_width: usize) {
+        if let Err(e) = fs::create_dir_all(out_dir) {
+            e_exit("FILE_CREATE", &format!("Failed to create directory {}: {}", out_dir.display(), e), 1);
+        }
+        let out_path = |path: &Path| out_dir.join(path.file_name().unwrap_or_default());
+        if out_path(r1) == out_path(r2) {
+            e_exit("SAMPLE", "Both mates have the same file name, the outputs would overwrite each other", 1);
+        }
+
+        let result = (|| {
+            let pairs = read_mate_pairs(r1, r2)?;
+            let create = |path: &Path| {
+                MultiFormatWriter::new(&out_path(path), line_width).map_err(|e| format!("Failed to create file: {}", e))
+            };
+            let (mut writer1, mut writer2) = (create(r1)?, create(r2)?);
+            let counts = sample_stream(pairs, size, seed, |(a, b)| {
+                writer1.write_record(a)
+                    .and_then(|_| writer2.write_record(b))
+                    .map_err(|e| format!("Write failed: {}", e))
+            })?;
+            writer1.flush()
+                .and_then(|_| writer2.flush())
+                .map_err(|e| format!("Write failed: {}", e))?;
+            Ok::<_, String>(counts)
+        })();
+
+        match result {
+            Ok((seen, kept)) => ok_println(
+                "Sample",
+                &format!("{} + {} ({} of {} pairs)", r1.display(), r2.display(), kept, seen),
+            ),
+            Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process files [{}, {}]: {}", r1.display(), r2.display(), e)),
+        }
+        ok_println("SAMPLE_COMPLETE", "");
+    }
+}
+
+/// Samples items of a stream and writes them in input order, returning the seen and kept counts
+fn sample_stream<T, I, W>(items: I, size: SampleSize, seed: Option<u64>, mut write: W) -> Result<(usize, usize), String>
+where
+    I: Iterator<Item = Result<T, String>>,
+    W: FnMut(&T) -> Result<(), String>,
+{
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut seen = 0;
+    match size {
+        SampleSize::Fraction(fraction) => {
+            let mut kept = 0;
+            for item in items {
+                let item = item?;
+                seen += 1;
+                if rng.r#gen::<f64>() < fraction {
+                    write(&item)?;
+                    kept += 1;
+                }
+            }
+            Ok((seen, kept))
+        }
+        SampleSize::Count(n) => {
+            // Reservoir sampling (algorithm R), indices restore the input order afterwards.
+            // `n` comes from the user, so only a bounded part of the reservoir is preallocated
+            let mut reservoir: Vec<(usize, T)> = Vec::with_capacity(n.min(1 << 16));
+            for item in items {
+                let item = item?;
+                if reservoir.len() < n {
+                    reservoir.push((seen, item));
+                } else {
+                    let slot = rng.gen_range(0..=seen);
+                    if slot < n {
+                        reservoir[slot] = (seen, item);
+                    }
+                }
+                seen += 1;
+            }
+            reservoir.sort_unstable_by_key(|(index, _)| *index);
+            for (_, item) in &reservoir {
+                write(item)?;
+            }
+            Ok((seen, reservoir.len()))
+        }
+    }
+}
+
+/// Paired-end operations: interleaving, deinterleaving and repairing mate files
+pub struct PairedEnd;
+
+impl PairedEnd {
+
+    /// Interleaves mate files into one file, R1 and R2 records alternating
+    ///
+    /// Mate names must match pair by pair (`/1` `/2` suffixes and Casava 1.8 descriptions
+    /// are understood); the first out-of-sync pair stops the file, see [`PairedEnd::repair`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - Mate files as (R1, R2) tuples, all written to the same output
+    /// * `output` - Path to the interleaved output file
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn interleave(pairs: Vec<(PathBuf, PathBuf)>, output: PathBuf, line_width: usize) {
+        let mut writer = match MultiFormatWriter::new(&output, line_width) {
+            Ok(w) => w,
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        };
+
+        for (r1, r2) in &pairs {
+            let result = read_mate_pairs(r1, r2).and_then(|mates| {
+                let mut count = 0;
+                for pair in mates {
+                    let (a, b) = pair?;
+                    count += 1;
+                    if !is_mate_pair(&a, &b) {
+                        return Err(out_of_sync(count, &a, &b));
+                    }
+                    writer.write_record(&a)
+                        .and_then(|_| writer.write_record(&b))
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                }
+                writer.flush().map_err(|e| format!("Write failed: {}", e))?;
+                Ok(count)
+            });
+
+            match result {
+                Ok(count) => ok_println("Interleave", &format!("{} + {} ({} pairs)", r1.display(), r2.display(), count)),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process files [{}, {}]: {}", r1.display(), r2.display(), e)),
+            }
+        }
+        ok_println("INTERLEAVE_COMPLETE", "");
+    }
+
+    /// Splits interleaved files into their mates
+    ///
+    /// Each input is written to `{stem}_1.{ext}` and `{stem}_2.{ext}` in `out_dir`.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Interleaved FASTA/FASTQ files
+    /// * `out_dir` - Directory receiving the mate files, created if missing
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn deinterleave(paths: Vec<PathBuf>, out_dir: PathBuf, line_width: usize) {
+        if let Err(e) = fs::create_dir_all(&out_dir) {
+            e_exit("FILE_CREATE", &format!("Failed to create directory {}: {}", out_dir.display(), e), 1);
+        }
+
+        for path in &paths {
+            let (stem, ext) = (file_prefix(path), first_extension(path));
+            let result = (|| {
+                let create = |mate: u8| {
+                    let out = out_dir.join(format!("{}_{}.{}", stem, mate, ext));
+                    MultiFormatWriter::new(&out, line_width).map_err(|e| format!("Failed to create file {}: {}", out.display(), e))
+                };
+                let (mut writer1, mut writer2) = (create(1)?, create(2)?);
+
+                let mut records = read_seq_records(path)?;
+                let mut count = 0;
+                while let Some(a) = records.next() {
+                    let a = a?;
+                    let b = records.next()
+                        .ok_or_else(|| format!("Odd number of records, {} has no mate", a.id))??;
+                    count += 1;
+                    if !is_mate_pair(&a, &b) {
+                        return Err(out_of_sync(count, &a, &b));
+                    }
+                    writer1.write_record(&a)
+                        .and_then(|_| writer2.write_record(&b))
+                        .map_err(|e| format!("Write failed: {}", e))?;
+                }
+                writer1.flush()
+                    .and_then(|_| writer2.flush())
+                    .map_err(|e| format!("Write failed: {}", e))?;
+                Ok(count)
+            })();
+
+            match result {
+                Ok(count) => ok_println("Deinterleave", &format!("{} ({} pairs)", path.display(), count)),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+        ok_println("DEINTERLEAVE_COMPLETE", "");
+    }
+
+    /// Re-pairs out-of-sync mate files by read name
+    ///
+    /// Both files are read alternately, holding unmatched mates until their partner shows up,
+    /// so memory grows only with the distance between mates. Repaired mates are written to
+    /// `out_dir` under their input file names; reads without a mate go to
+    /// `{stem}.singletons.{ext}` (named after R1) when `singletons` is set and are dropped otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `r1`, `r2` - Mate files to repair
+    /// * `out_dir` - Directory receiving the repaired files, created if missing
+    /// * `singletons` - Keep unpaired reads in a separate file
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn repair(r1: &Path, r2: &Path, out_dir: &Path, singletons: bool, line_width: usize) {
+        if let Err(e) = fs::create_dir_all(out_dir) {
+            e_exit("FILE_CREATE", &format!("Failed to create directory {}: {}", out_dir.display(), e), 1);
+        }
+        let out_path = |path: &Path| out_dir.join(path.file_name().unwrap_or_default());
+        if out_path(r1) == out_path(r2) {
+            e_exit("REPAIR", "Both mates have the same file name, the outputs would overwrite each other", 1);
+        }
+
+        let result = (|| {
+            let create = |path: &PathBuf| {
+                MultiFormatWriter::new(path, line_width).map_err(|e| format!("Failed to create file {}: {}", path.display(), e))
+            };
+            let (mut writer1, mut writer2) = (create(&out_path(r1))?, create(&out_path(r2))?);
+            let mut mates = [read_seq_records(r1)?, read_seq_records(r2)?];
+
+            // Unmatched reads of each side by read name, with their position in the file
+            let mut pending: [HashMap<String, (usize, SeqRecord)>; 2] = [HashMap::new(), HashMap::new()];
+            let mut unpaired: Vec<(usize, usize, SeqRecord)> = Vec::new();
+            let mut positions = [0, 0];
+            let mut finished = [false, false];
+            let (mut pairs, mut moved) = (0, 0);
+
+            while !(finished[0] && finished[1]) {
+                for side in 0..2 {
+                    if finished[side] {
+                        continue;
+                    }
+                    let Some(record) = mates[side].next() else {
+                        finished[side] = true;
+                        continue;
+                    };
+                    let record = record?;
+                    let position = positions[side];
+                    positions[side] += 1;
+                    let name = mate_name(&record.id).to_string();
+
+                    match pending[1 - side].remove(&name) {
+                        Some((other_position, other)) => {
+                            let (a, b) = if side == 0 { (&record, &other) } else { (&other, &record) };
+                            writer1.write_record(a)
+                                .and_then(|_| writer2.write_record(b))
+                                .map_err(|e| format!("Write failed: {}", e))?;
+                            pairs += 1;
+                            if other_position != position {
+                                moved += 1;
+                            }
+                        }
+                        None => {
+                            // A repeated name leaves the earlier read without a mate
+                            if let Some((p, old)) = pending[side].insert(name, (position, record)) {
+                                unpaired.push((side, p, old));
+                            }
+                        }
+                    }
+                }
+            }
+            writer1.flush()
+                .and_then(|_| writer2.flush())
+                .map_err(|e| format!("Write failed: {}", e))?;
+
+            for (side, reads) in pending.into_iter().enumerate() {
+                unpaired.extend(reads.into_values().map(|(p, record)| (side, p, record)));
+            }
+            if singletons && !unpaired.is_empty() {
+                unpaired.sort_unstable_by_key(|(side, p, _)| (*side, *p));
+                let path = out_dir.join(format!("{}.singletons.{}", file_prefix(r1), first_extension(r1)));
+                let mut writer = create(&path)?;
+                for (_, _, record) in &unpaired {
+                    writer.write_record(record).map_err(|e| format!("Write failed: {}", e))?;
+                }
+                writer.flush().map_err(|e| format!("Write failed: {}", e))?;
+            }
+            Ok::<_, String>((pairs, moved, unpaired.len()))
+        })();
+
+        match result {
+            Ok((pairs, moved, unpaired)) => ok_println(
+                "Repair",
+                &format!("{} + {} ({} pairs, {} re-paired, {} without mate)", r1.display(), r2.display(), pairs, moved, unpaired),
+            ),
+            Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process files [{}, {}]: {}", r1.display(), r2.display(), e)),
+        }
+        ok_println("REPAIR_COMPLETE", "");
+    }
+}
+
+/// Error message for the first pair whose mate names do not match
+fn out_of_sync(pair: usize, first: &SeqRecord, second: &SeqRecord) -> String {
+    format!(
+        "Mates out of sync at pair {} ({} / {}), use `process repair` to re-pair them by name",
+        pair, first.id, second.id
+    )
+}
+
+/// Trimming steps used by [`SeqTrim`], `None` or `0` disables a step
+///
+/// Steps run in field order. Quality steps only apply to FASTQ records.
+#[derive(Debug, Clone, Default)]
+pub struct TrimOptions {
+    pub head: usize,                      // Bases clipped from the 5' end
+    pub tail: usize,                      // Bases clipped from the 3' end
+    pub adapters: Vec<Vec<u8>>,           // Adapter sequences, cut at their first match
+    pub adapter_mismatches: usize,        // Mismatches allowed in a full-length adapter match
+    pub min_overlap: usize,               // Shortest adapter prefix matched at the 3' end
+    pub poly_g: Option<usize>,            // Minimum length of a trimmed poly-G tail
+    pub poly_a: Option<usize>,            // Minimum length of a trimmed poly-A tail
+    pub leading_qual: Option<u8>,         // Trim 5' bases below this Phred quality
+    pub trailing_qual: Option<u8>,        // Trim 3' bases below this Phred quality
+    pub window: Option<(usize, f64)>,     // Cut at the first window (size, mean Phred quality) below quality
+    pub min_len: usize,                   // Records shorter than this after trimming are removed
+}
+
+/// Per-file counts of [`SeqTrim`], reported by [`crate::info::InfoTrim`]
+#[derive(Debug, Clone, Default)]
+pub struct TrimStats {
+    pub reads_in: usize,
+    pub bases_in: usize,
+    pub reads_out: usize,
+    pub bases_out: usize,
+    pub clipped: usize,       // Bases removed by head/tail clipping
+    pub adapter_reads: usize, // Reads with an adapter match
+    pub adapter_bases: usize,
+    pub poly_reads: usize,    // Reads with a poly-G/poly-A tail
+    pub poly_bases: usize,
+    pub quality_bases: usize, // Bases removed by quality trimming
+    pub too_short: usize,     // Reads removed by the minimum length
+}
+
+/// Adapter, poly-X tail and quality trimming of sequence reads
+pub struct SeqTrim;
+
+impl SeqTrim {
+
+    /// Trims every record of the input files and writes the results to one output file
+    ///
+    /// Returns the counts of each input file, in input order and including files
+    /// without records, for the report of [`crate::info::InfoTrim`].
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTQ (or FASTA, without quality steps) files
+    /// * `output` - Path to the output file
+    /// * `options` - Trimming steps
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn trim(paths: &[PathBuf], output: PathBuf, options: &TrimOptions, line_width: usize) -> Vec<TrimStats> {
+        let mut stats = vec![TrimStats::default(); paths.len()];
+        let mut current = 0;
+
+        transform_records(paths, &output, "Trim", line_width, |path, mut record| {
+            // Records arrive in input order, so the current file only moves forward
+            while paths[current] != path {
+                current += 1;
+            }
+            let file_stats = &mut stats[current];
+
+            file_stats.reads_in += 1;
+            file_stats.bases_in += record.seq.len();
+            Self::trim_record(&mut record, options, file_stats);
+
+            if record.seq.len() < options.min_len.max(1) {
+                file_stats.too_short += 1;
+                return None;
+            }
+            file_stats.reads_out += 1;
+            file_stats.bases_out += record.seq.len();
+            Some(record)
+        });
+
+        ok_println("TRIM_COMPLETE", "");
+        stats
+    }
+
+    /// Applies all enabled trimming steps to one record
+    fn trim_record(record: &mut SeqRecord, options: &TrimOptions, stats: &mut TrimStats) {
+        let len = record.seq.len();
+        let mut start = options.head.min(len);
+        let mut end = len.saturating_sub(options.tail).max(start);
+        stats.clipped += len - (end - start);
+
+        if let Some(cut) = Self::find_adapter(&record.seq[start..end], options) {
+            stats.adapter_reads += 1;
+            stats.adapter_bases += end - start - cut;
+            end = start + cut;
+        }
+
+        let poly_end = [(b'G', options.poly_g), (b'A', options.poly_a)]
+            .into_iter()
+            .filter_map(|(base, min)| Some((base, min?)))
+            .fold(end, |end, (base, min)| {
+                let run = record.seq[start..end].iter().rev()
+                    .take_while(|b| b.to_ascii_uppercase() == base)
+                    .count();
+                if run >= min.max(1) { end - run } else { end }
+            });
+        if poly_end < end {
+            stats.poly_reads += 1;
+            stats.poly_bases += end - poly_end;
+            end = poly_end;
+        }
+
+        if let Some(qual) = &record.qual {
+            let before = end - start;
+            let phred = |i: usize| qual[i].saturating_sub(33);
+            if let Some(min) = options.leading_qual {
+                while start < end && phred(start) < min {
+                    start += 1;
+                }
+            }
+            if let Some(min) = options.trailing_qual {
+                while end > start && phred(end - 1) < min {
+                    end -= 1;
+                }
+            }
+            if let Some((size, min)) = options.window {
+                let size = size.max(1);
+                if end - start >= size {
+                    let mut sum: usize = (start..start + size).map(|i| phred(i) as usize).sum();
+                    let mut i = start;
+                    loop {
+                        if (sum as f64) < min * size as f64 {
+                            end = i;
+                            break;
+                        }
+                        if i + size >= end {
+                            break;
+                        }
+                        sum = sum + phred(i + size) as usize - phred(i) as usize;
+                        i += 1;
+                    }
+                }
+            }
+            stats.quality_bases += before - (end - start);
+        }
+
+        record.seq.truncate(end);
+        record.seq.drain(..start);
+        if let Some(qual) = record.qual.as_mut() {
+            qual.truncate(end);
+            qual.drain(..start);
+        }
+    }
+
+    /// Returns the position of the leftmost adapter match, full or as a prefix running off the 3' end
+    ///
+    /// A match of `overlap` bases allows `adapter_mismatches * overlap / adapter_len` mismatches.
+    fn find_adapter(seq: &[u8], options: &TrimOptions) -> Option<usize> {
+        let min_overlap = options.min_overlap.max(1);
+        (0..seq.len()).find(|&pos| {
+            options.adapters.iter().any(|adapter| {
+                let overlap = adapter.len().min(seq.len() - pos);
+                if overlap < min_overlap.min(adapter.len()) {
+                    return false;
+                }
+                let allowed = options.adapter_mismatches * overlap / adapter.len();
+                seq[pos..pos + overlap].iter()
+                    .zip(adapter)
+                    .filter(|(a, b)| !a.eq_ignore_ascii_case(b))
+                    .nth(allowed)
+                    .is_none()
+            })
+        })
+    }
+}
+
+/// How [`SeqRename`] derives the new IDs
+#[derive(Debug, Clone)]
+pub enum RenameRule {
+    Mapping(HashMap<String, String>), // Old ID to new ID, unlisted IDs are kept
+    Template(String),                 // `{file}`, `{num}` (1-based, per file), `{id}` and `{len}` placeholders
+    Regex(Regex, String),             // Pattern and replacement, `$1`/`${name}` refer to groups
+}
+
+/// Rewrites sequence IDs and annotation seqnames
+pub struct SeqRename;
+
+impl SeqRename {
+
+    /// Renames the records of the input files into one output file
+    ///
+    /// FASTA/FASTQ inputs get new record IDs; if the first input is a GFF3/GTF file,
+    /// the seqname column (and `##sequence-region` lines) are renamed instead, numbering
+    /// `{num}` by distinct seqname and taking `{len}` from `##sequence-region`.
+    /// Writing the applied renames with `map_output` and passing that file back as a
+    /// [`RenameRule::Mapping`] keeps annotations in sync with renamed sequences.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input files, either all FASTA/FASTQ or all GFF3/GTF
+    /// * `output` - Path to the output file
+    /// * `rule` - How new IDs are derived
+    /// * `keep_old` - Move the old ID to the start of the description (sequences only)
+    /// * `map_output` - Optional path of a TSV file receiving `old\tnew` lines
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn rename(paths: Vec<PathBuf>, output: PathBuf, rule: &RenameRule, keep_old: bool, map_output: Option<PathBuf>, line_width: usize) {
+        let Some(first) = paths.first() else {
+            e_exit("RENAME", "No input files given", 1);
+        };
+
+        let renames = match FileType::infer_file_type(first) {
+            FileType::Fasta | FileType::Fastq => Self::rename_sequences(&paths, &output, rule, keep_old, line_width),
+            FileType::Gff => Self::rename_annotations(&paths, &output, rule, GffType::GFF3),
+            FileType::Gtf => Self::rename_annotations(&paths, &output, rule, GffType::GTF2),
+            _ => e_exit("TYPE-ERROR", &format!("Unsupported format for rename: {:?}", first), 1),
+        };
+
+        let changed = renames.iter().filter(|(old, new)| old != new).count();
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (_, new) in &renames {
+            *seen.entry(new.as_str()).or_insert(0) += 1;
+        }
+        let duplicated = seen.values().filter(|&&n| n > 1).count();
+        ok_println("Renamed", &format!("{} of {} IDs", changed, renames.len()));
+        if duplicated > 0 {
+            e_println("RENAME", &format!("{} new IDs are used more than once", duplicated));
+        }
+
+        if let Some(map_output) = map_output {
+            let result = File::create(&map_output).and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                for (old, new) in &renames {
+                    writeln!(writer, "{}\t{}", old, new)?;
+                }
+                writer.flush()
+            });
+            match result {
+                Ok(_) => ok_println("Map", &format!("{}", map_output.display())),
+                Err(e) => e_println("WRITE_ERROR", &format!("Failed to write {}: {}", map_output.display(), e)),
+            }
+        }
+        ok_println("RENAME_COMPLETE", "");
+    }
+
+    /// Renames FASTA/FASTQ record IDs, returning the (old, new) pairs in output order
+    fn rename_sequences(paths: &[PathBuf], output: &PathBuf, rule: &RenameRule, keep_old: bool, line_width: usize) -> Vec<(String, String)> {
+        let mut renames: Vec<(String, String)> = Vec::new();
+        let mut current: Option<PathBuf> = None;
+        let mut n = 0;
+
+        transform_records(paths, output, "Rename", line_width, |path, mut record| {
+            if current.as_deref() != Some(path) {
+                current = Some(path.to_path_buf());
+                n = 0;
+            }
+            n += 1;
+
+            let new_id = Self::new_id(rule, &record.id, path, n, Some(record.seq.len()));
+            if keep_old && new_id != record.id {
+                record.desc = Some(match record.desc.take() {
+                    Some(desc) => format!("{} {}", record.id, desc),
+                    None => record.id.clone(),
+                });
+            }
+            renames.push((std::mem::replace(&mut record.id, new_id.clone()), new_id));
+            Some(record)
+        });
+        renames
+    }
+
+    /// Renames annotation seqnames, returning the (old, new) pairs of distinct seqnames
+    fn rename_annotations(paths: &[PathBuf], output: &Path, rule: &RenameRule, gff_type: GffType) -> Vec<(String, String)> {
+        let mut writer = match AnnotationWriter::new(output, gff_type) {
+            Ok(w) => w,
+            Err(e) => e_exit("FILE_CREATE", &format!("Failed to create file: {}", e), 1),
+        };
+        let mut renames: Vec<(String, String)> = Vec::new();
+        let mut directives: Vec<String> = Vec::new();
+
+        for path in paths {
+            let input_type = match FileType::infer_file_type(path) {
+                FileType::Gff => GffType::GFF3,
+                FileType::Gtf => GffType::GTF2,
+                _ => {
+                    e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: not a GFF/GTF file, skipped", path.display()));
+                    continue;
+                }
+            };
+
+            // Sequence lengths for `{len}`, from `##sequence-region seqid start end`
+            let file_directives = read_gff_directives(path);
+            let lengths: HashMap<String, usize> = file_directives.iter()
+                .filter_map(|d| {
+                    let mut fields = d.strip_prefix("##sequence-region")?.split_whitespace();
+                    let (id, _, end) = (fields.next()?, fields.next()?, fields.next()?);
+                    Some((id.to_string(), end.parse().ok()?))
+                })
+                .collect();
+
+            let result = read_annotations(path, input_type).and_then(|records| {
+                let mut file_names: HashMap<String, String> = HashMap::new();
+                let mut renamed = Vec::with_capacity(records.len());
+                for mut record in records {
+                    let old = record.seqname().to_string();
+                    let new = match file_names.get(&old) {
+                        Some(new) => new.clone(),
+                        None => {
+                            let n = file_names.len() + 1;
+                            let new = Self::new_id(rule, &old, path, n, lengths.get(&old).copied());
+                            file_names.insert(old.clone(), new.clone());
+                            renames.push((old, new.clone()));
+                            new
+                        }
+                    };
+                    *record.seqname_mut() = new;
+                    renamed.push(record);
+                }
+
+                for directive in &file_directives {
+                    let directive = match directive.strip_prefix("##sequence-region") {
+                        Some(rest) => {
+                            let mut fields: Vec<&str> = rest.split_whitespace().collect();
+                            if let Some(new) = fields.first().and_then(|id| file_names.get(*id)) {
+                                fields[0] = new;
+                            }
+                            format!("##sequence-region {}", fields.join(" "))
+                        }
+                        None => directive.clone(),
+                    };
+                    if !directives.contains(&directive) {
+                        writer.write_directive(&directive).map_err(|e| format!("Write failed: {}", e))?;
+                        directives.push(directive);
+                    }
+                }
+                for record in &renamed {
+                    writer.write(record).map_err(|e| format!("Write failed: {}", e))?;
+                }
+                Ok(renamed.len())
+            });
+
+            match result {
+                Ok(count) => ok_println("Rename", &format!("{} ({} records)", path.display(), count)),
+                Err(e) => e_println("PROCESS_ERROR", &format!("Failed to process file [{}]: {}", path.display(), e)),
+            }
+        }
+
+        if let Err(e) = writer.flush() {
+            e_exit("WRITE_ERROR", &format!("Failed to write renamed output: {}", e), 1);
+        }
+        renames
+    }
+
+    /// Derives the new ID of one record
+    fn new_id(rule: &RenameRule, id: &str, path: &Path, n: usize, len: Option<usize>) -> String {
+        match rule {
+            RenameRule::Mapping(map) => map.get(id).cloned().unwrap_or_else(|| id.to_string()),
+            RenameRule::Regex(pattern, replacement) => pattern.replace_all(id, replacement.as_str()).into_owned(),
+            RenameRule::Template(template) => {
+                let mut new_id = template
+                    .replace("{file}", &file_prefix(path))
+                    .replace("{num}", &n.to_string())
+                    .replace("{id}", id);
+                if new_id.contains("{len}") {
+                    let len = len.unwrap_or_else(|| e_exit(
+                        "RENAME",
+                        &format!("No length known for {} ({{len}} needs ##sequence-region lines in annotation files)", id),
+                        1,
+                    ));
+                    new_id = new_id.replace("{len}", &len.to_string());
+                }
+                new_id
+            }
+        }
+    }
+}
+
+/// Letter case applied by [`SeqReformat`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    Preserve, // Keep the input case, e.g. soft-masked (lower-case) regions
+    Upper,
+    Lower,
+}
+
+/// Rewrites FASTA/FASTQ files in a normalized layout
+pub struct SeqReformat;
+
+impl SeqReformat {
+
+    /// Rewrites every record of the input files into one output file
+    ///
+    /// Output always uses `\n` line endings, so Windows CRLF input is normalized,
+    /// and FASTA sequences are wrapped at `line_width`.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `case` - Letter case of the sequences
+    /// * `strip` - Remove whitespace and digits left inside sequence lines (with their qualities)
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn reformat(paths: Vec<PathBuf>, output: PathBuf, case: CaseMode, strip: bool, line_width: usize) {
+        let mut stripped = 0;
+        let total = transform_records(&paths, &output, "Reformat", line_width, |_, mut record| {
+            if strip {
+                let keep: Vec<bool> = record.seq.iter()
+                    .map(|b| !b.is_ascii_whitespace() && !b.is_ascii_digit())
+                    .collect();
+                if keep.contains(&false) {
+                    stripped += keep.iter().filter(|k| !**k).count();
+                    let mut flags = keep.iter();
+                    record.seq.retain(|_| *flags.next().unwrap());
+                    if let Some(qual) = record.qual.as_mut() {
+                        let mut flags = keep.iter();
+                        qual.retain(|_| flags.next().copied().unwrap_or(true));
+                    }
+                }
+            }
+            match case {
+                CaseMode::Preserve => {}
+                CaseMode::Upper => record.seq.make_ascii_uppercase(),
+                CaseMode::Lower => record.seq.make_ascii_lowercase(),
+            }
+            if let Some(desc) = record.desc.as_mut() {
+                desc.retain(|c| c != '\r');
+            }
+            Some(record)
+        });
+
+        ok_println("Reformatted", &format!("{} records", total));
+        if strip {
+            ok_println("Stripped", &format!("{} characters", stripped));
+        }
+        ok_println("REFORMAT_COMPLETE", "");
+    }
+}
+
+/// Size in bytes of a record written as FASTA (wrapped at `line_width`) or FASTQ
+fn record_size(record: &SeqRecord, line_width: usize) -> u64 {
+    let header = record.id.len() + record.desc.as_ref().map_or(0, |d| d.len() + 1) + 2;
+    let body = match &record.qual {
+        Some(qual) => record.seq.len() + qual.len() + 4,
+        None => {
+            let lines = line_wrap(line_width).map_or(1, |width| record.seq.len().div_ceil(width).max(1));
+            record.seq.len() + lines
+        }
+    };
+    (header + body) as u64
+}
+
+/// Replaces characters that are not safe in file names with `_`
+fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "._-+".contains(c) { c } else { '_' })
+        .collect();
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+/// Lower-cased extension of a path, used to name output files of the same format
+fn first_extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// How masked bases are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskStyle {
+    Soft, // Lower-case
+    Hard, // Replaced by `N`
+}
+
+/// Masking methods of [`SeqMask`], any combination can be used
+#[derive(Debug, Clone, Default)]
+pub struct MaskOptions {
+    pub dust: Option<(usize, f64)>,  // DUST window size and score threshold
+    pub intervals: Option<HashMap<String, Vec<(usize, usize)>>>, // 0-based half-open intervals per sequence ID
+    pub homopolymer: Option<usize>,  // Minimum homopolymer run length
+}
+
+/// Masks low-complexity regions, given intervals and homopolymer runs
+pub struct SeqMask;
+
+impl SeqMask {
+    /// Default DUST window, as in the original DUST program
+    pub const DUST_WINDOW: usize = 64;
+    /// Default DUST score threshold
+    pub const DUST_LEVEL: f64 = 20.0;
+
+    /// Masks every record of the input files into one output file
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `options` - Masking methods
+    /// * `style` - Soft (lower-case) or hard (`N`) masking
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn mask(paths: Vec<PathBuf>, output: PathBuf, options: &MaskOptions, style: MaskStyle, line_width: usize) {
+        let mut masked = 0;
+        let total = transform_records(&paths, &output, "Mask", line_width, |_, mut record| {
+            let mut mask = vec![false; record.seq.len()];
+            if let Some((window, level)) = options.dust {
+                Self::dust(&record.seq, window, level, &mut mask);
+            }
+            if let Some(intervals) = options.intervals.as_ref().and_then(|map| map.get(&record.id)) {
+                for &(start, end) in intervals {
+                    let end = end.min(mask.len());
+                    if start < end {
+                        mask[start..end].fill(true);
+                    }
+                }
+            }
+            if let Some(min_run) = options.homopolymer {
+                Self::homopolymers(&record.seq, min_run, &mut mask);
+            }
+
+            for (base, _) in record.seq.iter_mut().zip(&mask).filter(|(_, masked)| **masked) {
+                *base = match style {
+                    MaskStyle::Soft => base.to_ascii_lowercase(),
+                    MaskStyle::Hard => b'N',
+                };
+            }
+            masked += mask.iter().filter(|m| **m).count();
+            Some(record)
+        });
+
+        ok_println("Masked", &format!("{} bases in {} records", masked, total));
+        ok_println("MASK_COMPLETE", "");
+    }
+
+    /// Removes soft-masking by upper-casing every record; hard-masked bases cannot be restored
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Input FASTA/FASTQ files
+    /// * `output` - Path to the output file
+    /// * `line_width` - Residues per FASTA line, 0 for single-line sequences
+    pub fn unmask(paths: Vec<PathBuf>, output: PathBuf, line_width: usize) {
+        let mut unmasked = 0;
+        let total = transform_records(&paths, &output, "Unmask", line_width, |_, mut record| {
+            unmasked += record.seq.iter().filter(|b| b.is_ascii_lowercase()).count();
+            record.seq.make_ascii_uppercase();
+            Some(record)
+        });
+
+        ok_println("Unmasked", &format!("{} bases in {} records", unmasked, total));
+        ok_println("UNMASK_COMPLETE", "");
+    }
+
+    /// Loads mask intervals from a BED file (0-based) or a GFF3/GTF file (1-based)
+    pub fn load_intervals(path: &Path) -> Result<HashMap<String, Vec<(usize, usize)>>, String> {
+        let mut intervals: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        let file_type = FileType::infer_file_type(path);
+        if file_type == FileType::Gff || file_type == FileType::Gtf {
+            let gff_type = if file_type == FileType::Gtf { GffType::GTF2 } else { GffType::GFF3 };
+            for record in read_annotations(path, gff_type)? {
+                intervals.entry(record.seqname().to_string())
+                    .or_default()
+                    .push((record.start().saturating_sub(1) as usize, *record.end() as usize));
+            }
+            return Ok(intervals);
+        }
+
+        for record in read_bed(path)? {
+            intervals.entry(record.chrom().to_string())
+                .or_default()
+                .push((record.start() as usize, record.end() as usize));
+        }
+        Ok(intervals)
+    }
+
+    /// DUST low-complexity scoring over sliding windows
+    ///
+    /// The score of a window is `sum(c * (c - 1) / 2) / (l - 1)` over the counts `c` of its
+    /// `l` triplets; in windows scoring above `level` the span of repeated triplets is masked.
+    /// Triplets with bases other than A, C, G and T/U are not counted.
+    fn dust(seq: &[u8], window: usize, level: f64, mask: &mut [bool]) {
+        let triplet = |i: usize| -> Option<usize> {
+            seq[i..i + 3].iter().try_fold(0, |code, &base| {
+                let base = match base.to_ascii_uppercase() {
+                    b'A' => 0,
+                    b'C' => 1,
+                    b'G' => 2,
+                    b'T' | b'U' => 3,
+                    _ => return None,
+                };
+                Some(code * 4 + base)
+            })
+        };
+        if window.min(seq.len()) < 4 {
+            return;
+        }
+        let triplets = window.min(seq.len()) - 2;
+
+        let mut counts = [0usize; 64];
+        let mut pairs = 0; // sum(c * (c - 1) / 2)
+        for start in 0..=seq.len() - 2 - triplets {
+            if start == 0 {
+                for t in (0..triplets).filter_map(triplet) {
+                    pairs += counts[t];
+                    counts[t] += 1;
+                }
+            } else {
+                if let Some(t) = triplet(start - 1) {
+                    counts[t] -= 1;
+                    pairs -= counts[t];
+                }
+                if let Some(t) = triplet(start + triplets - 1) {
+                    pairs += counts[t];
+                    counts[t] += 1;
+                }
+            }
+            if pairs as f64 / (triplets - 1) as f64 > level {
+                // Mask from the first to the last repeated triplet, not the unique flanks
+                let repeated = |&i: &usize| triplet(i).is_some_and(|t| counts[t] > 1);
+                let mut positions = start..start + triplets;
+                if let (Some(first), Some(last)) = (positions.clone().find(repeated), positions.rfind(repeated)) {
+                    mask[first..last + 3].fill(true);
+                }
+            }
+        }
+    }
+
+    /// Marks runs of at least `min_run` identical bases, `N` runs excluded
+    fn homopolymers(seq: &[u8], min_run: usize, mask: &mut [bool]) {
+        let mut start = 0;
+        for i in 1..=seq.len() {
+            let same = i < seq.len() && seq[i].eq_ignore_ascii_case(&seq[start]);
+            if !same {
+                if i - start >= min_run.max(1) && !seq[start].eq_ignore_ascii_case(&b'N') {
+                    mask[start..i].fill(true);
+                }
+                start = i;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// GFF3 with a coding transcript on each strand; the `-` transcript has out-of-frame CDS parts
+    const GFF3: &str = "##gff-version 3
+chr1\ttest\tgene\t101\t900\t.\t+\t.\tID=g1;Name=ABC
+chr1\ttest\tmRNA\t101\t900\t.\t+\t.\tID=t1;Parent=g1
+chr1\ttest\texon\t101\t300\t.\t+\t.\tID=t1.exon1;Parent=t1
+chr1\ttest\texon\t501\t900\t.\t+\t.\tID=t1.exon2;Parent=t1
+chr1\ttest\tCDS\t151\t300\t.\t+\t0\tID=t1.cds;Parent=t1
+chr1\ttest\tCDS\t501\t700\t.\t+\t0\tID=t1.cds;Parent=t1
+chr1\ttest\tgene\t1001\t1600\t.\t-\t.\tID=g2
+chr1\ttest\tmRNA\t1001\t1600\t.\t-\t.\tID=t2;Parent=g2
+chr1\ttest\texon\t1001\t1200\t.\t-\t.\tID=t2.exon1;Parent=t2
+chr1\ttest\texon\t1401\t1600\t.\t-\t.\tID=t2.exon2;Parent=t2
+chr1\ttest\tCDS\t1101\t1200\t.\t-\t2\tID=t2.cds;Parent=t2
+chr1\ttest\tCDS\t1401\t1500\t.\t-\t0\tID=t2.cds;Parent=t2
+";
+
+    /// Empty scratch directory for one test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("seq_here_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn convert_annotations<F>(input: &Path, output: &Path, gff_type: GffType, converter: F)
+    where
+        F: FnOnce(&Path, &mut AnnotationWriter) -> Result<usize, String>,
+    {
+        let mut writer = AnnotationWriter::new(output, gff_type).unwrap();
+        converter(input, &mut writer).unwrap();
+        writer.flush().unwrap();
+    }
+
+    fn attribute<'a>(record: &'a gff::Record, key: &str) -> Option<&'a str> {
+        record.attributes().get(key).map(String::as_str)
+    }
+
+    /// (type, start, end, phase) of the children of `parent`, in file order
+    fn children(records: &[gff::Record], parent: &str) -> Vec<(String, u64, u64, Option<u8>)> {
+        records.iter()
+            .filter(|r| attribute(r, "Parent") == Some(parent))
+            .map(|r| {
+                let phase: Option<u8> = r.phase().clone().try_into().unwrap_or(None);
+                (r.feature_type().to_string(), *r.start(), *r.end(), phase)
+            })
+            .collect()
+    }
+
+    fn part(feature_type: &str, start: u64, end: u64, phase: Option<u8>) -> (String, u64, u64, Option<u8>) {
+        (feature_type.to_string(), start, end, phase)
+    }
+
+    fn find<'a>(records: &'a [gff::Record], id: &str) -> &'a gff::Record {
+        records.iter().find(|r| attribute(r, "ID") == Some(id)).unwrap()
+    }
+
+    #[test]
+    fn gff3_to_gtf_to_gff3_keeps_parents_and_phases() {
+        let dir = temp_dir("gff3_gtf");
+        let (input, gtf, output) = (dir.join("in.gff3"), dir.join("mid.gtf"), dir.join("out.gff3"));
+        fs::write(&input, GFF3).unwrap();
+
+        convert_annotations(&input, &gtf, GffType::GTF2, ConvertFormat::gff3_to_gtf);
+        let gtf_records = read_annotations(&gtf, GffType::GTF2).unwrap();
+        for record in gtf_records.iter().filter(|r| r.feature_type() == "exon" || r.feature_type() == "CDS") {
+            let transcript = attribute(record, "transcript_id").unwrap();
+            let gene = attribute(record, "gene_id").unwrap();
+            assert_eq!(gene, if transcript == "t1" { "g1" } else { "g2" });
+        }
+
+        convert_annotations(&gtf, &output, GffType::GFF3, ConvertFormat::gtf_to_gff3);
+        let records = read_annotations(&output, GffType::GFF3).unwrap();
+        assert_eq!(attribute(find(&records, "t1"), "Parent"), Some("g1"));
+        assert_eq!(attribute(find(&records, "t2"), "Parent"), Some("g2"));
+        assert_eq!(find(&records, "t1").feature_type(), "mRNA");
+        assert_eq!(attribute(find(&records, "g1"), "Name"), Some("ABC"));
+        assert_eq!(children(&records, "t1"), vec![
+            part("exon", 101, 300, None),
+            part("exon", 501, 900, None),
+            part("CDS", 151, 300, Some(0)),
+            part("CDS", 501, 700, Some(0)),
+        ]);
+        assert_eq!(children(&records, "t2"), vec![
+            part("exon", 1001, 1200, None),
+            part("exon", 1401, 1600, None),
+            part("CDS", 1101, 1200, Some(2)),
+            part("CDS", 1401, 1500, Some(0)),
+        ]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn gff3_to_bed12_to_gff3_keeps_blocks_and_thick_span() {
+        let dir = temp_dir("gff3_bed12");
+        let (input, bed_path, output) = (dir.join("in.gff3"), dir.join("mid.bed"), dir.join("out.gff3"));
+        fs::write(&input, GFF3).unwrap();
+
+        {
+            let mut writer = bed::Writer::to_file(&bed_path).unwrap();
+            assert_eq!(ConvertFormat::gff_to_bed12(&input, GffType::GFF3, &mut writer).unwrap(), 2);
+        }
+        let bed_records = read_bed(&bed_path).unwrap();
+        let fields = |record: &bed::Record| -> Vec<String> {
+            (3..12).map(|i| record.aux(i).unwrap_or_default().to_string()).collect()
+        };
+        assert_eq!((bed_records[0].start(), bed_records[0].end()), (100, 900));
+        assert_eq!(fields(&bed_records[0]), ["t1", "0", "+", "150", "700", "0", "2", "200,400,", "0,400,"]);
+        assert_eq!((bed_records[1].start(), bed_records[1].end()), (1000, 1600));
+        assert_eq!(fields(&bed_records[1]), ["t2", "0", "-", "1100", "1500", "0", "2", "200,200,", "0,400,"]);
+
+        convert_annotations(&bed_path, &output, GffType::GFF3, ConvertFormat::bed_to_gff3);
+        let records = read_annotations(&output, GffType::GFF3).unwrap();
+        assert_eq!(find(&records, "t1").feature_type(), "mRNA");
+        assert_eq!(children(&records, "t1"), vec![
+            part("exon", 101, 300, None),
+            part("exon", 501, 900, None),
+            part("CDS", 151, 300, Some(0)),
+            part("CDS", 501, 700, Some(0)),
+        ]);
+        assert_eq!(children(&records, "t2"), vec![
+            part("exon", 1001, 1200, None),
+            part("exon", 1401, 1600, None),
+            part("CDS", 1101, 1200, Some(2)),
+            part("CDS", 1401, 1500, Some(0)),
+        ]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn gff3_to_bed12_rejects_start_zero() {
+        let dir = temp_dir("gff3_bed12_zero");
+        let (input, bed_path) = (dir.join("in.gff3"), dir.join("out.bed"));
+        fs::write(&input, "##gff-version 3
+chr1\ttest\tmRNA\t1\t300\t.\t+\t.\tID=t1
+chr1\ttest\texon\t1\t300\t.\t+\t.\tParent=t1
+chr1\ttest\tCDS\t0\t300\t.\t+\t0\tParent=t1
+").unwrap();
+
+        let mut writer = bed::Writer::to_file(&bed_path).unwrap();
+        let error = ConvertFormat::gff_to_bed12(&input, GffType::GFF3, &mut writer).unwrap_err();
+        assert!(error.contains("starts at 0"), "{}", error);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn bed_to_gff3_writes_regions_and_transcripts() {
+        let dir = temp_dir("bed_gff3");
+        let (input, output) = (dir.join("in.bed"), dir.join("out.gff3"));
+        fs::write(&input, "chr2\t10\t50\tpeak1\t7\t+\n\
+            chr2\t100\t400\tnc1\t0\t-\t100\t100\t0\t2\t50,100,\t0,200,\n").unwrap();
+
+        convert_annotations(&input, &output, GffType::GFF3, ConvertFormat::bed_to_gff3);
+        let records = read_annotations(&output, GffType::GFF3).unwrap();
+
+        let region = &records[0];
+        assert_eq!((region.feature_type(), *region.start(), *region.end()), ("region", 11, 50));
+        assert_eq!(attribute(region, "Name"), Some("peak1"));
+        assert_eq!(attribute(region, "Parent"), None);
+
+        // A thick span of zero length means a non-coding transcript without CDS
+        let transcript = find(&records, "nc1");
+        assert_eq!((transcript.feature_type(), *transcript.start(), *transcript.end()), ("transcript", 101, 400));
+        assert_eq!(children(&records, "nc1"), vec![
+            part("exon", 101, 150, None),
+            part("exon", 301, 400, None),
+        ]);
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,415 @@
+//! Alignment
+//!
+//! Readers for the SAM and BAM alignment formats.
+//! BAM files are BGZF compressed, a series of gzip members, so they are read through
+//! [`MultiGzDecoder`]; the format is detected from the file content, not from its extension.
+//!
+
+use crate::utils::reverse_complement;
+use bio::io::{fasta, fastq};
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+
+/// SAM flag bits
+pub mod flag {
+    pub const PAIRED: u16 = 0x1;          // Template has multiple segments
+    pub const PROPER_PAIR: u16 = 0x2;     // Each segment properly aligned
+    pub const UNMAPPED: u16 = 0x4;        // Segment unmapped
+    pub const MATE_UNMAPPED: u16 = 0x8;   // Next segment unmapped
+    pub const REVERSE: u16 = 0x10;        // Sequence reverse complemented
+    pub const MATE_REVERSE: u16 = 0x20;   // Next segment reverse complemented
+    pub const READ1: u16 = 0x40;          // First segment in the template
+    pub const READ2: u16 = 0x80;          // Last segment in the template
+    pub const SECONDARY: u16 = 0x100;     // Secondary alignment
+    pub const QC_FAIL: u16 = 0x200;       // Not passing quality controls
+    pub const DUPLICATE: u16 = 0x400;     // PCR or optical duplicate
+    pub const SUPPLEMENTARY: u16 = 0x800; // Supplementary alignment
+}
+
+/// MAPQ value meaning the mapping quality is not available
+pub const MAPQ_UNAVAILABLE: u8 = 255;
+
+/// CIGAR operations in BAM code order
+const CIGAR_OPS: &[u8; 9] = b"MIDNSHP=X";
+
+/// 4-bit BAM sequence codes
+const SEQ_CODES: &[u8; 16] = b"=ACMGRSVTWYHKDBN";
+
+/// A single alignment line
+#[derive(Debug, Clone, Default)]
+pub struct SamRecord {
+    pub qname: String,
+    pub flag: u16,
+    pub rname: Option<String>,  // Reference name, `None` for `*`
+    pub pos: u64,               // 1-based leftmost mapping position, 0 if unavailable
+    pub mapq: u8,
+    pub cigar: Vec<(u32, u8)>,  // (length, operation) pairs, operations from `MIDNSHP=X`
+    pub rnext: Option<String>,  // Mate reference name, `=` resolved to `rname`
+    pub seq: Vec<u8>,           // Read sequence as stored, empty for `*`
+    pub qual: Vec<u8>,          // Phred+33 qualities, empty for `*`
+}
+
+impl SamRecord {
+    /// Whether all bits of `bits` are set in the flag, see [`flag`]
+    pub fn has(&self, bits: u16) -> bool {
+        self.flag & bits == bits
+    }
+
+    /// Whether the read is unmapped
+    pub fn is_unmapped(&self) -> bool {
+        self.has(flag::UNMAPPED)
+    }
+
+    /// Whether this is the primary line of the read (neither secondary nor supplementary)
+    pub fn is_primary(&self) -> bool {
+        self.flag & (flag::SECONDARY | flag::SUPPLEMENTARY) == 0
+    }
+
+    /// Number of reference bases covered by the alignment, from the `MDN=X` operations
+    pub fn reference_len(&self) -> u64 {
+        self.cigar.iter()
+            .filter(|(_, op)| b"MDN=X".contains(op))
+            .map(|&(len, _)| len as u64)
+            .sum()
+    }
+
+    /// Rightmost reference position of the alignment (1-based, inclusive)
+    pub fn reference_end(&self) -> u64 {
+        self.pos + self.reference_len().max(1) - 1
+    }
+
+    /// Read name with a `/1` or `/2` suffix for the mates of a pair
+    pub fn read_name(&self) -> String {
+        match (self.has(flag::PAIRED), self.has(flag::READ1), self.has(flag::READ2)) {
+            (true, true, false) => format!("{}/1", self.qname),
+            (true, false, true) => format!("{}/2", self.qname),
+            _ => self.qname.clone(),
+        }
+    }
+
+    /// Sequence and qualities in the orientation the read was sequenced in
+    ///
+    /// Reverse-strand alignments store the reverse complement, which is undone here.
+    /// Missing qualities are filled with `missing_qual`.
+    pub fn original_read(&self, missing_qual: u8) -> (Vec<u8>, Vec<u8>) {
+        let mut qual = if self.qual.is_empty() {
+            vec![missing_qual; self.seq.len()]
+        } else {
+            self.qual.clone()
+        };
+        if self.has(flag::REVERSE) {
+            qual.reverse();
+            (reverse_complement(&self.seq), qual)
+        } else {
+            (self.seq.clone(), qual)
+        }
+    }
+
+    /// The read as a FASTQ record, see [`SamRecord::original_read`]
+    pub fn to_fastq(&self, missing_qual: u8) -> fastq::Record {
+        let (seq, qual) = self.original_read(missing_qual);
+        fastq::Record::with_attrs(&self.read_name(), None, &seq, &qual)
+    }
+
+    /// The read as a FASTA record, see [`SamRecord::original_read`]
+    pub fn to_fasta(&self) -> fasta::Record {
+        let (seq, _) = self.original_read(b'!');
+        fasta::Record::with_attrs(&self.read_name(), None, &seq)
+    }
+
+    /// Parses a tab-separated SAM alignment line
+    fn parse(line: &str) -> Result<SamRecord, String> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 11 {
+            return Err(format!("Expected 11 SAM columns, found {}", fields.len()));
+        }
+        let number = |i: usize, name: &str| -> Result<u64, String> {
+            fields[i].parse::<u64>().map_err(|_| format!("Invalid {}: {}", name, fields[i]))
+        };
+        let name = |value: &str| (value != "*").then(|| value.to_string());
+
+        let rname = name(fields[2]);
+        let rnext = match fields[6] {
+            "=" => rname.clone(),
+            value => name(value),
+        };
+        Ok(SamRecord {
+            qname: fields[0].to_string(),
+            flag: number(1, "FLAG")? as u16,
+            rname,
+            pos: number(3, "POS")?,
+            mapq: number(4, "MAPQ")?.min(MAPQ_UNAVAILABLE as u64) as u8,
+            cigar: parse_cigar(fields[5])?,
+            rnext,
+            seq: if fields[9] == "*" { Vec::new() } else { fields[9].as_bytes().to_ascii_uppercase() },
+            qual: if fields[10] == "*" { Vec::new() } else { fields[10].as_bytes().to_vec() },
+        })
+    }
+}
+
+/// Parses a CIGAR string such as `10S90M`, `*` giving an empty CIGAR
+fn parse_cigar(cigar: &str) -> Result<Vec<(u32, u8)>, String> {
+    if cigar == "*" {
+        return Ok(Vec::new());
+    }
+    let mut ops = Vec::new();
+    let mut len: u32 = 0;
+    for b in cigar.bytes() {
+        if b.is_ascii_digit() {
+            len = len.checked_mul(10)
+                .and_then(|l| l.checked_add((b - b'0') as u32))
+                .ok_or_else(|| format!("Invalid CIGAR: {}", cigar))?;
+        } else if CIGAR_OPS.contains(&b) {
+            ops.push((len, b));
+            len = 0;
+        } else {
+            return Err(format!("Invalid CIGAR: {}", cigar));
+        }
+    }
+    Ok(ops)
+}
+
+/// A reference region such as `chr1`, `chr1:1000` or `chr1:1,000-2,000`
+///
+/// Positions are 1-based and inclusive, as in samtools.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub name: String,
+    pub start: u64,
+    pub end: Option<u64>, // `None` for the end of the reference
+}
+
+impl Region {
+    /// Parses a region string
+    ///
+    /// # Arguments
+    /// * `region` - `name`, `name:start` or `name:start-end`, thousands separators allowed
+    pub fn parse(region: &str) -> Result<Region, String> {
+        let invalid = || format!("Invalid region: {} (expected name[:start[-end]])", region);
+        let Some((name, range)) = region.rsplit_once(':') else {
+            return Ok(Region { name: region.to_string(), start: 1, end: None });
+        };
+        let position = |value: &str| value.replace(',', "").parse::<u64>().map_err(|_| invalid());
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (position(start)?, Some(position(end)?)),
+            None => (position(range)?, None),
+        };
+        if name.is_empty() || start == 0 || end.is_some_and(|end| end < start) {
+            return Err(invalid());
+        }
+        Ok(Region { name: name.to_string(), start, end })
+    }
+
+    /// Whether a mapped alignment overlaps the region
+    pub fn overlaps(&self, record: &SamRecord) -> bool {
+        !record.is_unmapped()
+            && record.pos > 0
+            && record.rname.as_deref() == Some(self.name.as_str())
+            && record.reference_end() >= self.start
+            && self.end.is_none_or(|end| record.pos <= end)
+    }
+}
+
+/// Underlying input of an [`AlignmentReader`]
+enum Input {
+    Sam {
+        lines: io::Lines<Box<dyn BufRead + Send>>,
+        pending: Option<String>, // First alignment line, read while parsing the header
+    },
+    Bam(Box<dyn BufRead + Send>),
+}
+
+/// SAM/BAM reader
+///
+/// Plain and gzip-compressed SAM as well as BGZF-compressed BAM are accepted.
+pub struct AlignmentReader {
+    input: Input,
+    pub references: Vec<(String, u64)>, // Reference names and lengths from the header
+}
+
+impl AlignmentReader {
+    /// Opens a SAM or BAM file and reads its header
+    ///
+    /// # Arguments
+    /// * `path` - Path to the alignment file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let gzip = file.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+        let mut reader: Box<dyn BufRead + Send> = if gzip {
+            Box::new(BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Box::new(file)
+        };
+
+        // BAM content starts with `BAM\1`, anything else is read as SAM text
+        let mut magic = Vec::with_capacity(4);
+        reader.by_ref().take(4).read_to_end(&mut magic)?;
+        if magic == b"BAM\x01" {
+            let references = Self::read_bam_header(&mut reader)?;
+            return Ok(Self { input: Input::Bam(reader), references });
+        }
+
+        let reader: Box<dyn BufRead + Send> = Box::new(Cursor::new(magic).chain(reader));
+        let mut lines = reader.lines();
+        let mut references = Vec::new();
+        let mut pending = None;
+        for line in lines.by_ref() {
+            let line = line?;
+            if !line.starts_with('@') {
+                pending = Some(line);
+                break;
+            }
+            if let Some(reference) = Self::parse_sq_line(&line) {
+                references.push(reference);
+            }
+        }
+        Ok(Self { input: Input::Sam { lines, pending }, references })
+    }
+
+    /// Iterates over all alignments
+    pub fn records(self) -> AlignmentRecords {
+        AlignmentRecords { reader: self }
+    }
+
+    /// Reference name and length of a `@SQ` header line
+    fn parse_sq_line(line: &str) -> Option<(String, u64)> {
+        let (mut name, mut len) = (None, 0);
+        for field in line.strip_prefix("@SQ\t")?.split('\t') {
+            if let Some(value) = field.strip_prefix("SN:") {
+                name = Some(value.to_string());
+            } else if let Some(value) = field.strip_prefix("LN:") {
+                len = value.parse().unwrap_or(0);
+            }
+        }
+        name.map(|name| (name, len))
+    }
+
+    /// Reads the BAM header after the magic string, returning the reference list
+    fn read_bam_header(reader: &mut impl Read) -> io::Result<Vec<(String, u64)>> {
+        let text_len = read_i32(reader)?;
+        io::copy(&mut reader.by_ref().take(text_len.max(0) as u64), &mut io::sink())?;
+
+        let n_ref = read_i32(reader)?;
+        let mut references = Vec::with_capacity(n_ref.max(0) as usize);
+        for _ in 0..n_ref {
+            let name_len = read_i32(reader)?.max(0) as usize;
+            let mut name = vec![0; name_len];
+            reader.read_exact(&mut name)?;
+            let len = read_i32(reader)?;
+            name.retain(|&b| b != 0);
+            references.push((String::from_utf8_lossy(&name).into_owned(), len.max(0) as u64));
+        }
+        Ok(references)
+    }
+
+    /// Reads the next alignment, returning `None` at the end of the input
+    fn read_record(&mut self) -> Option<Result<SamRecord, String>> {
+        match &mut self.input {
+            Input::Sam { lines, pending } => loop {
+                let line = match pending.take() {
+                    Some(line) => line,
+                    None => match lines.next()? {
+                        Ok(line) => line,
+                        Err(e) => return Some(Err(e.to_string())),
+                    },
+                };
+                if !line.trim().is_empty() {
+                    return Some(SamRecord::parse(line.trim_end_matches('\r')));
+                }
+            },
+            Input::Bam(reader) => {
+                let mut size = [0; 4];
+                match reader.read_exact(&mut size) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+                    Err(e) => return Some(Err(e.to_string())),
+                }
+                let mut block = vec![0; i32::from_le_bytes(size).max(0) as usize];
+                if let Err(e) = reader.read_exact(&mut block) {
+                    return Some(Err(format!("Truncated BAM record: {}", e)));
+                }
+                Some(Self::parse_bam_record(&block, &self.references))
+            }
+        }
+    }
+
+    /// Decodes a BAM alignment block (without its size prefix)
+    fn parse_bam_record(block: &[u8], references: &[(String, u64)]) -> Result<SamRecord, String> {
+        let truncated = || "Truncated BAM record".to_string();
+        let i32_at = |i: usize| block.get(i..i + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        let u16_at = |i: usize| block.get(i..i + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        let reference = |id: i32| usize::try_from(id).ok()
+            .and_then(|id| references.get(id))
+            .map(|(name, _)| name.clone());
+
+        let ref_id = i32_at(0).ok_or_else(truncated)?;
+        let pos = i32_at(4).ok_or_else(truncated)?;
+        let name_len = *block.get(8).ok_or_else(truncated)? as usize;
+        let mapq = *block.get(9).ok_or_else(truncated)?;
+        let n_cigar = u16_at(12).ok_or_else(truncated)? as usize;
+        let flag = u16_at(14).ok_or_else(truncated)?;
+        let seq_len = i32_at(16).ok_or_else(truncated)?.max(0) as usize;
+        let next_ref_id = i32_at(20).ok_or_else(truncated)?;
+
+        let mut offset = 32;
+        let name = block.get(offset..offset + name_len).ok_or_else(truncated)?;
+        let qname = String::from_utf8_lossy(name.strip_suffix(&[0]).unwrap_or(name)).into_owned();
+        offset += name_len;
+
+        let cigar = block.get(offset..offset + 4 * n_cigar).ok_or_else(truncated)?
+            .chunks_exact(4)
+            .map(|op| {
+                let value = u32::from_le_bytes([op[0], op[1], op[2], op[3]]);
+                (value >> 4, *CIGAR_OPS.get((value & 0xf) as usize).unwrap_or(&b'M'))
+            })
+            .collect();
+        offset += 4 * n_cigar;
+
+        let packed = block.get(offset..offset + seq_len.div_ceil(2)).ok_or_else(truncated)?;
+        let seq = (0..seq_len)
+            .map(|i| SEQ_CODES[((packed[i / 2] >> (4 * (1 - i % 2))) & 0xf) as usize])
+            .collect();
+        offset += seq_len.div_ceil(2);
+
+        let qual = block.get(offset..offset + seq_len).ok_or_else(truncated)?;
+        let qual = if qual.first().is_none_or(|&q| q == 0xff) {
+            Vec::new()
+        } else {
+            qual.iter().map(|q| q.saturating_add(33)).collect()
+        };
+
+        Ok(SamRecord {
+            qname,
+            flag,
+            rname: reference(ref_id),
+            pos: (pos + 1).max(0) as u64,
+            mapq,
+            cigar,
+            rnext: reference(next_ref_id),
+            seq,
+            qual,
+        })
+    }
+}
+
+/// Iterator over the alignments of an [`AlignmentReader`]
+pub struct AlignmentRecords {
+    reader: AlignmentReader,
+}
+
+impl Iterator for AlignmentRecords {
+    type Item = Result<SamRecord, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.read_record()
+    }
+}
+
+/// Reads a little-endian `i32`
+fn read_i32(reader: &mut impl Read) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
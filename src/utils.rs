@@ -1,21 +1,31 @@
-/// Utils
-///
-/// The module has some useful functions for bioinformatics file handling and sequence analysis.
-///
+//! Utils
+//!
+//! The module has some useful functions for bioinformatics file handling and sequence analysis.
+//!
 
 use std::{fs, io};
+use std::cmp::Ordering;
 use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use crate::error::e_exit;
-use bio::io::{fasta, fastq, gff};
-use bio::io::gff::GffType;
+use bio::bio_types::strand::Strand;
+use bio::io::{bed, fasta, fastq, gff};
+use bio::io::gff::{GffType, Phase};
 
 /// Enumeration of supported bioinformatics file types
 /// Used for file type detection and handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     Fasta,  // FASTA sequence files (.fa, .fasta)
     Fastq,  // FASTQ sequence files (.fq, .fastq)
     Gff,    // GFF annotation files (.gff, .gff3)
+    Gtf,    // GTF annotation files (.gtf)
+    GenBank, // GenBank flat files (.gb, .gbk)
+    Embl,   // EMBL flat files (.embl)
+    Bed,    // BED interval files (.bed)
+    Sam,    // SAM alignment files (.sam)
+    Bam,    // BAM alignment files (.bam)
     Unknown, // Unrecognized file format
 }
 
@@ -23,16 +33,22 @@ impl FileType {
     /// Infers the biological file type based on the file extension.
     /// 
     /// # Arguments
-    /// * `path` - PathBuf pointing to the file to analyze
+    /// * `path` - Path pointing to the file to analyze
     ///
     /// # Returns
     /// * `FileType` enum representing the detected file type
-    pub fn infer_file_type(path: &PathBuf) -> FileType {
+    pub fn infer_file_type(path: &Path) -> FileType {
         path.extension()
             .and_then(|ext| ext.to_str())
             .map(|ext| match ext.to_lowercase().as_str() {
                 "fa" | "fasta" | "pep" => FileType::Fasta,      // DNA/protein sequence files
                 "gff" | "gff3" => FileType::Gff,                // Gene feature format
+                "gtf" => FileType::Gtf,                         // Gene transfer format
+                "gb" | "gbk" | "gbff" => FileType::GenBank,     // GenBank flat file
+                "embl" => FileType::Embl,                       // EMBL flat file
+                "bed" => FileType::Bed,                         // Browser Extensible Data intervals
+                "sam" => FileType::Sam,                         // Sequence Alignment/Map
+                "bam" => FileType::Bam,                         // Binary (BGZF) SAM
                 "fq" | "fastq" => FileType::Fastq,              // Sequence with quality scores
                 _ => FileType::Unknown
             })
@@ -49,22 +65,465 @@ pub struct MultiFormatWriter {
     pub gff: gff::Writer<File>,   // For writing GFF/GTF format files
 }
 
+/// Default width of FASTA sequence lines
+pub const DEFAULT_LINE_WIDTH: usize = 60;
+
+/// Converts a FASTA line width to the form `fasta::Writer::set_linewrap` expects, 0 for single-line sequences
+pub fn line_wrap(width: usize) -> Option<usize> {
+    match width {
+        0 => None,
+        width => Some(width),
+    }
+}
+
 impl MultiFormatWriter {
     /// Creates a new MultiFormatWriter that can write to different biological file formats.
     ///
     /// # Arguments
     /// * `path` - PathBuf indicating where to create the output file
+    /// * `line_width` - Residues per FASTA line, 0 writes each sequence on a single line
     ///
     /// # Returns
     /// * `io::Result<Self>` - The writer instance or an IO error
-    pub fn new(path: &PathBuf) -> io::Result<Self> {
+    pub fn new(path: &PathBuf, line_width: usize) -> io::Result<Self> {
         let file = File::create(path)?;
+        let mut fa = fasta::Writer::new(file.try_clone()?);
+        fa.set_linewrap(line_wrap(line_width));
         Ok(Self {
-            fa: fasta::Writer::new(file.try_clone()?),
+            fa,
             gff: gff::Writer::new(file.try_clone()?, GffType::GFF3),   // Default to GFF3 format
             fq: fastq::Writer::new(file),
         })
     }
+
+    /// Writes a sequence record, as FASTQ when it carries qualities and as FASTA otherwise.
+    ///
+    /// # Arguments
+    /// * `record` - The record to write
+    pub fn write_record(&mut self, record: &SeqRecord) -> io::Result<()> {
+        match &record.qual {
+            Some(qual) => self.fq.write(&record.id, record.desc.as_deref(), &record.seq, qual),
+            None => self.fa.write(&record.id, record.desc.as_deref(), &record.seq),
+        }
+    }
+
+    /// Flushes the FASTA and FASTQ writers
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.fa.flush()?;
+        self.fq.flush()
+    }
+}
+
+
+/// A FASTA or FASTQ record, owned so operations can modify it in place.
+///
+/// `qual` is `Some` for FASTQ records and `None` for FASTA records.
+#[derive(Debug, Clone, Default)]
+pub struct SeqRecord {
+    pub id: String,
+    pub desc: Option<String>,
+    pub seq: Vec<u8>,
+    pub qual: Option<Vec<u8>>,
+}
+
+impl From<fasta::Record> for SeqRecord {
+    fn from(record: fasta::Record) -> Self {
+        Self {
+            id: record.id().to_string(),
+            desc: record.desc().map(String::from),
+            seq: record.seq().to_vec(),
+            qual: None,
+        }
+    }
+}
+
+impl From<fastq::Record> for SeqRecord {
+    fn from(record: fastq::Record) -> Self {
+        Self {
+            id: record.id().to_string(),
+            desc: record.desc().map(String::from),
+            seq: record.seq().to_vec(),
+            qual: Some(record.qual().to_vec()),
+        }
+    }
+}
+
+/// Iterator over the records of a FASTA or FASTQ file
+pub type SeqRecords = Box<dyn Iterator<Item = Result<SeqRecord, String>>>;
+
+/// Opens a FASTA or FASTQ file, chosen by extension, and streams its records.
+///
+/// # Arguments
+/// * `path` - Path of the sequence file
+///
+/// # Returns
+/// * `Result<SeqRecords, String>` - The record iterator, or an error for unreadable or non-sequence files
+pub fn read_seq_records(path: &Path) -> Result<SeqRecords, String> {
+    match FileType::infer_file_type(path) {
+        FileType::Fasta => {
+            let reader = fasta::Reader::from_file(path)
+                .map_err(|e| format!("Failed to read FASTA file: {} - {}", path.display(), e))?;
+            Ok(Box::new(reader.records().map(|r| {
+                r.map(SeqRecord::from).map_err(|e| format!("Failed to parse FASTA: {}", e))
+            })))
+        }
+        FileType::Fastq => {
+            let reader = fastq::Reader::from_file(path)
+                .map_err(|e| format!("Failed to read FASTQ file: {} - {}", path.display(), e))?;
+            Ok(Box::new(reader.records().map(|r| {
+                r.map(SeqRecord::from).map_err(|e| format!("Failed to parse FASTQ: {}", e))
+            })))
+        }
+        _ => Err(format!("Not a FASTA/FASTQ file: {}", path.display())),
+    }
+}
+
+/// Iterator over the mate pairs of two paired-end files
+pub type SeqPairs = Box<dyn Iterator<Item = Result<(SeqRecord, SeqRecord), String>>>;
+
+/// Opens the two mate files of a paired-end dataset and streams their records side by side.
+///
+/// Records are paired by position; a file running out before the other yields an error.
+/// Use [`is_mate_pair`] to verify that the names of each pair match.
+///
+/// # Arguments
+/// * `r1` - File with the first mates
+/// * `r2` - File with the second mates
+pub fn read_mate_pairs(r1: &Path, r2: &Path) -> Result<SeqPairs, String> {
+    let mut mates1 = read_seq_records(r1)?;
+    let mut mates2 = read_seq_records(r2)?;
+    Ok(Box::new(std::iter::from_fn(move || match (mates1.next(), mates2.next()) {
+        (None, None) => None,
+        (Some(a), Some(b)) => Some(a.and_then(|a| Ok((a, b?)))),
+        _ => Some(Err("Mate files have different numbers of records".to_string())),
+    })))
+}
+
+/// Read name shared by both mates: the ID without a trailing `/1` or `/2`.
+///
+/// Casava 1.8 IDs (`@EAS139:136:FC706VJ:2:2104:15343:197393 1:Y:18:ATCACG`) keep the mate
+/// number in the description, so they are returned unchanged.
+pub fn mate_name(id: &str) -> &str {
+    id.strip_suffix("/1")
+        .or_else(|| id.strip_suffix("/2"))
+        .unwrap_or(id)
+}
+
+/// Mate number of a read, from a `/1` `/2` ID suffix or a Casava 1.8 description (`1:N:0:...`).
+pub fn mate_number(record: &SeqRecord) -> Option<u8> {
+    let from_suffix = match record.id.as_bytes() {
+        [.., b'/', b'1'] => Some(1),
+        [.., b'/', b'2'] => Some(2),
+        _ => None,
+    };
+    from_suffix.or_else(|| {
+        let comment = record.desc.as_deref()?.split_whitespace().next()?;
+        match comment.split(':').collect::<Vec<_>>().as_slice() {
+            ["1", _, _, ..] => Some(1),
+            ["2", _, _, ..] => Some(2),
+            _ => None,
+        }
+    })
+}
+
+/// Returns whether two records are the first and second mate of the same read.
+///
+/// Names must match once `/1` `/2` suffixes are removed; mate numbers are checked when both are known.
+pub fn is_mate_pair(first: &SeqRecord, second: &SeqRecord) -> bool {
+    mate_name(&first.id) == mate_name(&second.id)
+        && match (mate_number(first), mate_number(second)) {
+            (Some(a), Some(b)) => a == 1 && b == 2,
+            _ => true,
+        }
+}
+
+
+/// Annotation writer for GFF3 and GTF files.
+///
+/// Unlike `bio::io::gff::Writer`, attribute values are encoded the way each format expects
+/// (percent-encoding for GFF3, quoted values for GTF) and attributes are written in a stable order.
+pub struct AnnotationWriter {
+    inner: BufWriter<File>,
+    gff_type: GffType,
+}
+
+impl AnnotationWriter {
+    /// Creates the output file, starting it with `##gff-version 3` for GFF3 output.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the output file
+    /// * `gff_type` - `GffType::GFF3` or `GffType::GTF2`
+    ///
+    /// # Returns
+    /// * `io::Result<Self>` - The writer instance or an IO error
+    pub fn new(path: &Path, gff_type: GffType) -> io::Result<Self> {
+        let mut inner = BufWriter::new(File::create(path)?);
+        if gff_type == GffType::GFF3 {
+            writeln!(inner, "##gff-version 3")?;
+        }
+        Ok(Self { inner, gff_type })
+    }
+
+    /// Writes one annotation record.
+    ///
+    /// `ID`/`Parent` (GFF3) or `gene_id`/`transcript_id` (GTF) come first,
+    /// the remaining attributes follow in alphabetical order.
+    pub fn write(&mut self, record: &gff::Record) -> io::Result<()> {
+        let mut attributes: Vec<(&String, &Vec<String>)> = record.attributes().iter_all().collect();
+        attributes.sort_by_key(|(key, _)| (attribute_rank(key), key.to_string()));
+
+        let attributes = match self.gff_type {
+            GffType::GTF2 => attributes
+                .iter()
+                .flat_map(|(key, values)| {
+                    values.iter().map(move |v| format!("{} \"{}\";", key, v.replace('"', "'")))
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => attributes
+                .iter()
+                .map(|(key, values)| {
+                    let values: Vec<String> = values.iter().map(|v| escape_gff3(v)).collect();
+                    format!("{}={}", key, values.join(","))
+                })
+                .collect::<Vec<_>>()
+                .join(";"),
+        };
+
+        // `Record::score` only parses integer scores, so the raw column is read from a copy
+        let score = match record.clone().score_mut().as_str() {
+            "" => ".".to_string(),
+            raw => raw.to_string(),
+        };
+        let strand = match record.strand() {
+            Some(Strand::Forward) => "+",
+            Some(Strand::Reverse) => "-",
+            _ => ".",
+        };
+        let phase: Option<u8> = record.phase().clone().try_into().unwrap_or(None);
+        let phase = phase.map_or(".".to_string(), |p| p.to_string());
+
+        writeln!(
+            self.inner,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            record.seqname(),
+            record.source(),
+            record.feature_type(),
+            record.start(),
+            record.end(),
+            score,
+            strand,
+            phase,
+            if attributes.is_empty() { "." } else { &attributes },
+        )
+    }
+
+    /// Writes a `##` directive or `#` comment line as is
+    pub fn write_directive(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.inner, "{}", line.trim_end())
+    }
+
+    /// Flushes the buffered output to disk
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Sort rank of an attribute key: identifiers first, everything else after
+fn attribute_rank(key: &str) -> u8 {
+    match key {
+        "ID" | "gene_id" => 0,
+        "Parent" | "transcript_id" => 1,
+        "Name" => 2,
+        _ => 3,
+    }
+}
+
+/// Percent-encodes the characters reserved in GFF3 attribute values
+fn escape_gff3(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace(';', "%3B")
+        .replace('=', "%3D")
+        .replace(',', "%2C")
+        .replace('\t', "%09")
+}
+
+/// Decodes the percent-encoded characters of a GFF3 attribute value
+fn unescape_gff3(value: &str) -> String {
+    if !value.contains('%') {
+        return value.to_string();
+    }
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Reads all records of a GFF3 or GTF file.
+///
+/// GFF3 is read with `bio::io::gff::Reader`. GTF lines are split by hand because
+/// the bio reader cannot handle quoted attribute values containing spaces.
+///
+/// # Arguments
+/// * `path` - Path of the annotation file
+/// * `gff_type` - `GffType::GFF3` or `GffType::GTF2`
+///
+/// # Returns
+/// * `Result<Vec<gff::Record>, String>` - The records or a description of the error
+pub fn read_annotations(path: &Path, gff_type: GffType) -> Result<Vec<gff::Record>, String> {
+    if gff_type != GffType::GTF2 {
+        let mut reader = gff::Reader::from_file(path, gff_type)
+            .map_err(|e| format!("Failed to read annotation file: {} - {}", path.display(), e))?;
+        return reader.records()
+            .map(|r| {
+                let mut record = r.map_err(|e| format!("Failed to parse GFF: {}", e))?;
+                for (_, values) in record.attributes_mut().iter_all_mut() {
+                    for value in values.iter_mut() {
+                        *value = unescape_gff3(value);
+                    }
+                }
+                Ok(record)
+            })
+            .collect();
+    }
+
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to read annotation file: {} - {}", path.display(), e))?;
+    let mut records = Vec::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read GTF: {}", e))?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let record = parse_gtf_line(&line)
+            .ok_or_else(|| format!("Failed to parse GTF line {}: {}", n + 1, line))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Reads the intervals of a BED file
+///
+/// `track` and `browser` lines, `#` comments and blank lines are skipped. Records keep their
+/// optional columns (name, score, strand, ...) whatever their number.
+///
+/// # Arguments
+/// * `path` - Path of the BED file
+///
+/// # Returns
+/// * `Result<Vec<bed::Record>, String>` - The records or a description of the error
+pub fn read_bed(path: &Path) -> Result<Vec<bed::Record>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to read BED file: {} - {}", path.display(), e))?;
+    let mut records = Vec::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read BED: {}", e))?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') || line.starts_with("track") || line.starts_with("browser") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let coordinate = |i: usize| fields.get(i).and_then(|f| f.trim().parse::<u64>().ok());
+        let (Some(start), Some(end)) = (coordinate(1), coordinate(2)) else {
+            return Err(format!("Failed to parse BED line {}: {}", n + 1, line));
+        };
+        if start > end {
+            return Err(format!("BED line {} starts after its end: {}", n + 1, line));
+        }
+        let mut record = bed::Record::new();
+        record.set_chrom(fields[0]);
+        record.set_start(start);
+        record.set_end(end);
+        for field in &fields[3..] {
+            record.push_aux(field);
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Reads the `##` directives of a GFF file, in file order
+///
+/// The `##gff-version` line and `###` separators are left out, and reading stops
+/// at an embedded `##FASTA` section. Unreadable files yield no directives.
+///
+/// # Arguments
+/// * `path` - Path of the annotation file
+///
+/// # Returns
+/// * `Vec<String>` - The directive lines, without line endings
+pub fn read_gff_directives(path: &Path) -> Vec<String> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .map(|line| line.trim_end().to_string())
+        .take_while(|line| !line.starts_with("##FASTA"))
+        .filter(|line| line.starts_with("##") && !line.starts_with("##gff-version") && line != "###")
+        .collect()
+}
+
+/// Parses one GTF line into a GFF record
+fn parse_gtf_line(line: &str) -> Option<gff::Record> {
+    let fields: Vec<&str> = line.splitn(9, '\t').collect();
+    if fields.len() < 8 {
+        return None;
+    }
+
+    let mut record = gff::Record::new();
+    *record.seqname_mut() = fields[0].to_string();
+    *record.source_mut() = fields[1].to_string();
+    *record.feature_type_mut() = fields[2].to_string();
+    *record.start_mut() = fields[3].parse().ok()?;
+    *record.end_mut() = fields[4].parse().ok()?;
+    *record.score_mut() = fields[5].to_string();
+    *record.strand_mut() = fields[6].to_string();
+    *record.phase_mut() = Phase::from(fields[7].parse::<u8>().ok());
+
+    // Split on ';' outside of quoted values
+    let raw_attributes = fields.get(8).unwrap_or(&"");
+    let mut pairs = Vec::new();
+    let (mut in_quotes, mut begin) = (false, 0);
+    for (i, c) in raw_attributes.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                pairs.push(&raw_attributes[begin..i]);
+                begin = i + 1;
+            }
+            _ => {}
+        }
+    }
+    pairs.push(&raw_attributes[begin..]);
+
+    for pair in pairs {
+        let pair = pair.trim();
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = pair.split_once(char::is_whitespace).unwrap_or((pair, ""));
+        record.attributes_mut().insert(key.to_string(), value.trim().trim_matches('"').to_string());
+    }
+    Some(record)
 }
 
 /// Determines file type based on file extension
@@ -80,6 +539,8 @@ pub fn try_file_type_ext(file: &Path) -> Result<String, Box<dyn std::error::Erro
         "fasta" | "fa" => Ok("fasta".to_string()),  // FASTA sequence files
         "fastq" | "fq" => Ok("fastq".to_string()),  // FASTQ sequence files
         "gff" | "gtf" => Ok("gff".to_string()),     // Gene annotation files
+        "gb" | "gbk" => Ok("genbank".to_string()),  // GenBank flat files
+        "embl" => Ok("embl".to_string()),           // EMBL flat files
         "bed" => Ok("bed".to_string()),             // Browser Extensible Data format
         "sam" => Ok("sam".to_string()),             // Sequence Alignment/Map format
         "bam" => Ok("bam".to_string()),             // Binary version of SAM
@@ -87,10 +548,145 @@ pub fn try_file_type_ext(file: &Path) -> Result<String, Box<dyn std::error::Erro
     }
 }
 
+/// Sequence alphabets, checked case-insensitively so soft-masked sequences are valid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    Dna,     // IUPAC nucleotide codes with T
+    Rna,     // IUPAC nucleotide codes with U
+    Protein, // IUPAC amino acid codes, including B, Z, J, U, O, X and stop `*`
+}
+
+impl Alphabet {
+    /// All alphabets, from the most to the least specific
+    pub const ALL: [Alphabet; 3] = [Alphabet::Dna, Alphabet::Rna, Alphabet::Protein];
+
+    /// Detection confidence below which a sequence is undecided
+    pub const MIN_CONFIDENCE: f64 = 0.6;
+
+    /// Name of the alphabet, as used in reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Alphabet::Dna => "DNA",
+            Alphabet::Rna => "RNA",
+            Alphabet::Protein => "Protein",
+        }
+    }
+
+    /// Detects the alphabet of a sequence from its composition
+    ///
+    /// Returns the alphabet with a confidence between [`Alphabet::MIN_CONFIDENCE`] and 1, or
+    /// `None` for empty sequences, sequences with characters outside every alphabet and
+    /// undecided sequences (such as the short `ACGTA`). See [`AlphabetDetector`].
+    ///
+    /// # Arguments
+    /// * `seq` - Sequence to classify, of any case
+    pub fn detect(seq: &[u8]) -> Option<(Alphabet, f64)> {
+        Self::guess(seq).filter(|(_, confidence)| *confidence >= Self::MIN_CONFIDENCE)
+    }
+
+    /// Best guess of [`Alphabet::detect`], also returned when its confidence is too low to decide
+    pub fn guess(seq: &[u8]) -> Option<(Alphabet, f64)> {
+        let mut detector = AlphabetDetector::default();
+        detector.add(seq);
+        detector.finish()
+    }
+
+    /// Returns whether a sequence character belongs to the alphabet; gaps (`-`, `.`) always do
+    pub fn contains(&self, c: u8) -> bool {
+        let c = c.to_ascii_uppercase();
+        if c == b'-' || c == b'.' {
+            return true;
+        }
+        match self {
+            Alphabet::Dna => b"ACGTRYKMSWBDHVN".contains(&c),
+            Alphabet::Rna => b"ACGURYKMSWBDHVN".contains(&c),
+            Alphabet::Protein => c.is_ascii_uppercase() || c == b'*',
+        }
+    }
+}
+
+/// Incremental alphabet detection, for sequences read line by line
+///
+/// A residue outside the IUPAC nucleotide codes makes a sequence protein, with a confidence
+/// that grows with the share of residues other than `A`, `C`, `G`, `T`/`U` and `N`.
+/// Otherwise nucleotide and protein are both possible: the nucleotide score grows with the
+/// share of those core residues, the protein score with the share of the others, both are
+/// scaled down for short sequences and the higher one wins. Nucleotide sequences are RNA when
+/// `U` outnumbers `T`, and mixing both lowers the confidence. Gaps (`-`, `.`) are ignored.
+///
+/// So the short `ACGTA` gets 0.5 and stays below [`Alphabet::MIN_CONFIDENCE`], while DNA
+/// with many ambiguity codes is still DNA.
+#[derive(Debug, Clone, Default)]
+pub struct AlphabetDetector {
+    residues: usize,       // Letters and stops, gaps excluded
+    core: usize,           // A, C, G, T, U and N
+    t: usize,
+    u: usize,
+    non_nucleotide: usize, // Residues that are not IUPAC nucleotide codes
+    invalid: usize,        // Characters outside every alphabet
+}
+
+impl AlphabetDetector {
+    /// Share of A, C, G, T and N residues in natural proteins, used to scale protein confidence
+    const PROTEIN_CORE_SHARE: f64 = 0.26;
+    /// Residue count giving half weight to composition-only evidence
+    const HALF_WEIGHT_LENGTH: f64 = 5.0;
+
+    /// Adds a chunk of sequence to the counts
+    pub fn add(&mut self, seq: &[u8]) {
+        for &c in seq {
+            let c = c.to_ascii_uppercase();
+            match c {
+                b'-' | b'.' => continue,
+                b'T' => self.t += 1,
+                b'U' => self.u += 1,
+                _ => {}
+            }
+            if !Alphabet::Protein.contains(c) {
+                self.invalid += 1;
+                continue;
+            }
+            self.residues += 1;
+            if b"ACGTUN".contains(&c) {
+                self.core += 1;
+            } else if !Alphabet::Dna.contains(c) {
+                self.non_nucleotide += 1;
+            }
+        }
+    }
+
+    /// Returns the best guess and its confidence, see [`Alphabet::guess`]
+    pub fn finish(&self) -> Option<(Alphabet, f64)> {
+        if self.invalid > 0 || self.residues == 0 {
+            return None;
+        }
+        let residues = self.residues as f64;
+        let core_share = self.core as f64 / residues;
+        let protein_share = ((1.0 - core_share) / (1.0 - Self::PROTEIN_CORE_SHARE)).min(1.0);
+        if self.non_nucleotide > 0 {
+            return Some((Alphabet::Protein, protein_share));
+        }
+
+        let length_weight = residues / (residues + Self::HALF_WEIGHT_LENGTH);
+        let protein = protein_share * length_weight;
+        let mut nucleotide = (1.0 + core_share) / 2.0 * length_weight;
+        if self.t > 0 && self.u > 0 {
+            nucleotide *= self.t.abs_diff(self.u) as f64 / (self.t + self.u) as f64;
+        }
+
+        if nucleotide >= protein {
+            let alphabet = if self.u > self.t { Alphabet::Rna } else { Alphabet::Dna };
+            Some((alphabet, nucleotide))
+        } else {
+            Some((Alphabet::Protein, protein))
+        }
+    }
+}
+
 /// Determines the biological sequence type by analyzing its content
 ///
-/// Uses a heuristic approach to check if the sequence conforms to DNA, RNA, or protein alphabets.
-/// In case of ambiguity, prioritizes DNA > RNA > Protein classification.
+/// Uses [`Alphabet::detect`], so IUPAC ambiguity codes, gaps, stops and soft-masked
+/// (lower-case) residues are understood.
 ///
 /// # Arguments
 /// * `seq` - Byte slice containing the sequence to analyze
@@ -98,106 +694,211 @@ pub fn try_file_type_ext(file: &Path) -> Result<String, Box<dyn std::error::Erro
 /// # Returns
 /// * `String` - The determined sequence type ("DNA", "RNA", "Protein", or "Unknown")
 pub fn try_seq_type_seq(seq: &[u8]) -> String {
-    if seq.is_empty() {
-        eprintln!("Empty sequence");
+    match Alphabet::detect(seq) {
+        Some((alphabet, _)) => alphabet.name().to_string(),
+        None => "Unknown".to_string(),
     }
+}
 
-    // Track validity flags for each sequence type
-    let (mut is_dna, mut is_rna, mut is_protein) = (true, true, true);
-    
+/// Complements a single nucleotide, keeping its case
+///
+/// Supports the IUPAC ambiguity codes (R/Y, K/M, B/V, D/H, S, W, N).
+/// Gaps and unknown characters are returned unchanged.
+///
+/// # Arguments
+/// * `base` - The nucleotide to complement
+/// * `rna` - Complement `A` to `U` instead of `T`
+///
+/// # Returns
+/// * `u8` - The complementary nucleotide
+pub fn complement_base(base: u8, rna: bool) -> u8 {
+    let complement = match base.to_ascii_uppercase() {
+        b'A' if rna => b'U',
+        b'A' => b'T',
+        b'T' | b'U' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        other => other, // S, W, N, gaps and unknown characters
+    };
+
+    if base.is_ascii_lowercase() {
+        complement.to_ascii_lowercase()
+    } else {
+        complement
+    }
+}
+
+/// Returns true if the nucleotide sequence is RNA, i.e. contains `U` but no `T`
+pub fn is_rna_seq(seq: &[u8]) -> bool {
+    let (mut has_u, mut has_t) = (false, false);
     for &c in seq {
-        let c_upper = c.to_ascii_uppercase();
-        let mut valid_in_any = false;
-
-        // Check DNA validity - valid chars are A, T, C, G, N
-        if is_dna {
-            // N is commonly used as a placeholder for unknown nucleotides
-            if matches!(c_upper, b'A' | b'T' | b'C' | b'G' | b'N') {
-                valid_in_any = true;
-            } else {
-                is_dna = false;
-            }
+        match c.to_ascii_uppercase() {
+            b'U' => has_u = true,
+            b'T' => has_t = true,
+            _ => {}
         }
+    }
+    has_u && !has_t
+}
 
-        // Check RNA validity - valid chars are A, U, C, G
-        if is_rna {
-            if matches!(c_upper, b'A' | b'U' | b'C' | b'G') {
-                valid_in_any = true;
-            } else {
-                is_rna = false;
-            }
-        }
+/// Complements a nucleotide sequence, keeping case and RNA/DNA type
+///
+/// # Arguments
+/// * `seq` - Byte slice containing the nucleotide sequence
+///
+/// # Returns
+/// * `Vec<u8>` - The complementary sequence
+pub fn complement_seq(seq: &[u8]) -> Vec<u8> {
+    let rna = is_rna_seq(seq);
+    seq.iter().map(|&c| complement_base(c, rna)).collect()
+}
 
-        // Check Protein validity - standard amino acid codes
-        if is_protein {
-            if matches!(
-                c_upper,
-                b'A' | b'R'
-                    | b'N'
-                    | b'D'
-                    | b'C'
-                    | b'E'
-                    | b'Q'
-                    | b'G'
-                    | b'H'
-                    | b'I'
-                    | b'L'
-                    | b'K'
-                    | b'M'
-                    | b'F'
-                    | b'P'
-                    | b'S'
-                    | b'T'
-                    | b'W'
-                    | b'Y'
-                    | b'V'
-                    | b'B'
-                    | b'J'
-                    | b'O'
-                    | b'U'
-                    | b'X'
-                    | b'Z'
-            ) {
-                valid_in_any = true;
-            } else {
-                is_protein = false;
-            }
-        }
+/// Reverse-complements a nucleotide sequence, keeping case and RNA/DNA type
+///
+/// # Arguments
+/// * `seq` - Byte slice containing the nucleotide sequence
+///
+/// # Returns
+/// * `Vec<u8>` - The reverse complement of the sequence
+pub fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    let rna = is_rna_seq(seq);
+    seq.iter().rev().map(|&c| complement_base(c, rna)).collect()
+}
 
-        // Early exit if invalid character
-        if !valid_in_any {
-            eprintln!("Invalid character: {}", c as char);
-        }
-        
-        // Optimization: Early exit if only one type remains valid
-        if only_one_true(is_dna, is_rna, is_protein) {
-            break;
+/// NCBI genetic code used to translate codons
+///
+/// Tables are given as the NCBI amino acid and start strings, with codons in `TCAG` order.
+#[derive(Debug, Clone, Copy)]
+pub struct GeneticCode {
+    pub id: u8,
+    amino_acids: &'static [u8; 64],
+    starts: &'static [u8; 64], // `M` marks the start codons of the table
+}
+
+impl GeneticCode {
+    /// Supported NCBI translation tables: (id, name, amino acids, starts)
+    const TABLES: [(u8, &'static str, &'static [u8; 64], &'static [u8; 64]); 7] = [
+        (1, "Standard",
+         b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+         b"---M------**--*----M---------------M----------------------------"),
+        (2, "Vertebrate Mitochondrial",
+         b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+         b"----------**--------------------MMMM----------**---M------------"),
+        (3, "Yeast Mitochondrial",
+         b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+         b"----------**----------------------MM---------------M------------"),
+        (4, "Mold, Protozoan and Coelenterate Mitochondrial; Mycoplasma",
+         b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+         b"--MM------**-------M------------MMMM---------------M------------"),
+        (5, "Invertebrate Mitochondrial",
+         b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+         b"---M------**--------------------MMMM---------------M------------"),
+        (6, "Ciliate, Dasycladacean and Hexamita Nuclear",
+         b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+         b"--------------*--------------------M----------------------------"),
+        (11, "Bacterial, Archaeal and Plant Plastid",
+         b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+         b"---M------**--*----M------------MMMM---------------M------------"),
+    ];
+
+    /// Returns the NCBI translation table with this id
+    pub fn new(id: u8) -> Result<Self, String> {
+        Self::TABLES.iter()
+            .find(|(table_id, ..)| *table_id == id)
+            .map(|&(id, _, amino_acids, starts)| Self { id, amino_acids, starts })
+            .ok_or_else(|| {
+                let ids: Vec<String> = Self::TABLES.iter().map(|(id, name, ..)| format!("{} ({})", id, name)).collect();
+                format!("Unsupported genetic code {}, supported: {}", id, ids.join(", "))
+            })
+    }
+
+    /// Index of a codon in `TCAG` order, `None` for ambiguous bases
+    fn index(codon: &[u8]) -> Option<usize> {
+        codon.iter().try_fold(0, |index, &base| {
+            let code = match base.to_ascii_uppercase() {
+                b'T' | b'U' => 0,
+                b'C' => 1,
+                b'A' => 2,
+                b'G' => 3,
+                _ => return None,
+            };
+            Some(index * 4 + code)
+        })
+    }
+
+    /// Translates one codon, `*` for stops and `X` for codons with ambiguous bases
+    pub fn translate_codon(&self, codon: &[u8]) -> u8 {
+        Self::index(codon).map_or(b'X', |i| self.amino_acids[i])
+    }
+
+    /// Returns whether the codon is `ATG`, or any start codon of the table with `alternative`
+    pub fn is_start(&self, codon: &[u8], alternative: bool) -> bool {
+        match Self::index(codon) {
+            Some(i) if alternative => self.starts[i] == b'M',
+            Some(i) => i == 35, // ATG
+            None => false,
         }
     }
 
-    // Determine result by priority: DNA > RNA > Protein
-    // If sequence could be multiple types (e.g., is_dna && is_protein),
-    // we classify as the highest priority type
-    if is_dna {
-        "DNA".into()
-    } else if is_rna {
-        "RNA".into()
-    } else if is_protein {
-        "Protein".into()
-    } else {
-        "Unknown sequence type".into()
+    /// Translates a coding sequence codon by codon, a trailing partial codon is ignored
+    pub fn translate(&self, seq: &[u8]) -> Vec<u8> {
+        seq.chunks_exact(3).map(|codon| self.translate_codon(codon)).collect()
     }
 }
 
-/// Utility function that returns true only if exactly one of the three boolean parameters is true
+/// Compares two names in natural order, so that `chr2` sorts before `chr10`
+///
+/// Runs of digits are compared by numeric value, everything else byte by byte.
 ///
 /// # Arguments
-/// * `a`, `b`, `c` - Three boolean values to check
+/// * `a`, `b` - The names to compare
 ///
 /// # Returns
-/// * `bool` - True if exactly one parameter is true, false otherwise
-fn only_one_true(a: bool, b: bool, c: bool) -> bool {
-    (a as u8 + b as u8 + c as u8) == 1
+/// * `Ordering` - The natural ordering of `a` and `b`
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let (start_a, start_b) = (i, j);
+            while i < a.len() && a[i].is_ascii_digit() {
+                i += 1;
+            }
+            while j < b.len() && b[j].is_ascii_digit() {
+                j += 1;
+            }
+            // Compare numerically without overflow: strip leading zeros, then by length and digits
+            let num_a = trim_leading_zeros(&a[start_a..i]);
+            let num_b = trim_leading_zeros(&b[start_b..j]);
+            let ord = num_a.len().cmp(&num_b.len()).then_with(|| num_a.cmp(num_b));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        } else {
+            if a[i] != b[j] {
+                return a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    (a.len() - i).cmp(&(b.len() - j)).then_with(|| a.cmp(b))
+}
+
+/// Strips the leading `0` digits of a digit run, keeping at least one digit
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&d| d == b'0').count();
+    &digits[zeros.min(digits.len().saturating_sub(1))..]
 }
 
 /// Writes string content to a file at the specified path
@@ -215,12 +916,12 @@ pub fn write_file<P: AsRef<Path>>(path: P, content: &str) {
 /// is meant to be a directory rather than actually checking the filesystem.
 ///
 /// # Arguments
-/// * `path` - PathBuf to analyze
+/// * `path` - Path to analyze
 ///
 /// # Returns
 /// * `bool` - True if the path likely represents a directory, false otherwise
-pub fn is_directory_path(path: &PathBuf) -> bool {
-    path.extension().map_or(true, |ext| {
+pub fn is_directory_path(path: &Path) -> bool {
+    path.extension().is_none_or(|ext| {
         ext.is_empty() || path.as_os_str().to_str().unwrap().ends_with('.')
     })
 }
@@ -242,3 +943,46 @@ pub fn create_file_with_dir(path: &Path) {
         e_exit("FILE", &format!("Unable to create file: {}", e), 1);
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_nucleotide_like_sequence_is_undecided() {
+        assert_eq!(Alphabet::detect(b"ACGTA"), None);
+        let (alphabet, confidence) = Alphabet::guess(b"ACGTA").unwrap();
+        assert_eq!(alphabet, Alphabet::Dna);
+        assert!(confidence < Alphabet::MIN_CONFIDENCE, "{}", confidence);
+        assert_eq!(try_seq_type_seq(b"ACGTA"), "Unknown");
+    }
+
+    #[test]
+    fn iupac_heavy_dna_stays_dna() {
+        // 14 of 40 residues are ambiguity codes, above the former 25% limit
+        let seq = b"ACGTRYKMACGTSWRYACGTACGTBDHVACGTRYACGTAC";
+        let (alphabet, confidence) = Alphabet::detect(seq).unwrap();
+        assert_eq!(alphabet, Alphabet::Dna);
+        assert!(confidence >= Alphabet::MIN_CONFIDENCE, "{}", confidence);
+        assert_eq!(Alphabet::detect(&seq.to_ascii_lowercase()).map(|(a, _)| a), Some(Alphabet::Dna));
+    }
+
+    #[test]
+    fn mixed_t_and_u_follow_the_majority() {
+        let (alphabet, _) = Alphabet::detect(b"ACGUACGUACGUACGUACGUACGUACGUACGUACGUACGT").unwrap();
+        assert_eq!(alphabet, Alphabet::Rna);
+        let (alphabet, _) = Alphabet::detect(b"ACGTACGTACGTACGTACGTACGTACGTACGTACGTACGU").unwrap();
+        assert_eq!(alphabet, Alphabet::Dna);
+        // As many T as U: a nucleotide sequence of undecided type, never a protein
+        assert_eq!(Alphabet::detect(b"ACGTACGUACGTACGUACGTACGUACGTACGU"), None);
+        assert_ne!(Alphabet::guess(b"ACGTACGUACGTACGUACGTACGUACGTACGU").map(|(a, _)| a), Some(Alphabet::Protein));
+    }
+
+    #[test]
+    fn peptides_are_protein() {
+        assert_eq!(Alphabet::detect(b"MKVLAAGIILLSPQE*").map(|(a, _)| a), Some(Alphabet::Protein));
+        assert_eq!(Alphabet::detect(b"MKWRSDHYVMKWRSDHYVMKWRSDHYV").map(|(a, _)| a), Some(Alphabet::Protein));
+        assert_eq!(Alphabet::detect(b"AC-GT.A"), None);
+        assert_eq!(Alphabet::detect(b"ACGT1"), None);
+    }
+}
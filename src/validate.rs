@@ -0,0 +1,406 @@
+//! Validate
+//!
+//! Lint checks for FASTA, FASTQ and GFF3/GTF files.
+//! Files are scanned line by line, so every issue points at the line it was found on.
+//!
+
+use crate::error::e_exit;
+use crate::utils::{read_seq_records, Alphabet, AlphabetDetector, FileType};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Severity of an [`Issue`], only errors make validation fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A problem found in an input file
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub file: PathBuf,
+    pub line: usize,        // 1-based line number, 0 for file-level issues
+    pub severity: Severity,
+    pub code: &'static str, // Stable issue code, e.g. "DUPLICATE_ID"
+    pub message: String,
+}
+
+impl Issue {
+    /// Formats the issue as a tab-separated `file line severity code message` line
+    pub fn to_tsv(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.file.display(),
+            self.line,
+            self.severity.name(),
+            self.code,
+            self.message.replace(['\t', '\n'], " ")
+        )
+    }
+}
+
+/// Checks sequence and annotation files
+pub struct Validator;
+
+impl Validator {
+
+    /// Validates the given files and writes the issue list
+    ///
+    /// Issues are written as TSV (`file`, `line`, `severity`, `code`, `message`) to `output`,
+    /// or to stdout if `output` is `None`. The process exits with code 1 if any error was found.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - FASTA, FASTQ, GFF3 or GTF files
+    /// * `alphabet` - Declared alphabet of the sequences, any alphabet is accepted if `None`
+    /// * `reference` - FASTA/FASTQ file giving sequence lengths for annotation coordinates
+    /// * `output` - Path of the issue list
+    pub fn validate(paths: Vec<PathBuf>, alphabet: Option<Alphabet>, reference: Option<PathBuf>, output: Option<PathBuf>) {
+        let lengths = reference.map(|path| match Self::sequence_lengths(&path) {
+            Ok(lengths) => lengths,
+            Err(e) => e_exit("FILE-READ", &format!("Failed to read reference {}: {}", path.display(), e), 1),
+        });
+
+        let issues = Self::check_files(&paths, alphabet, lengths.as_ref());
+
+        let result = match &output {
+            Some(path) => File::create(path).and_then(|file| Self::write_issues(&issues, file)),
+            None => Self::write_issues(&issues, io::stdout().lock()),
+        };
+        if let Err(e) = result {
+            e_exit("WRITE_ERROR", &format!("Failed to write issues: {}", e), 1);
+        }
+
+        let errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+        let summary = format!("{} files, {} errors, {} warnings", paths.len(), errors, issues.len() - errors);
+        if errors > 0 {
+            e_exit("VALIDATE", &summary, 1);
+        }
+        eprintln!("Valid: {}", summary);
+    }
+
+    /// Runs the checks matching each file type and returns all issues in file order
+    pub fn check_files(paths: &[PathBuf], alphabet: Option<Alphabet>, lengths: Option<&HashMap<String, usize>>) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        for path in paths {
+            let mut file_issues = Issues { file: path.clone(), list: Vec::new() };
+            let result = match FileType::infer_file_type(path) {
+                FileType::Fasta => Self::check_fasta(path, alphabet, &mut file_issues),
+                FileType::Fastq => Self::check_fastq(path, alphabet, &mut file_issues),
+                FileType::Gff => Self::check_gff(path, false, lengths, &mut file_issues),
+                FileType::Gtf => Self::check_gff(path, true, lengths, &mut file_issues),
+                _ => {
+                    file_issues.warning(0, "UNSUPPORTED", "File type is not validated".to_string());
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                file_issues.error(0, "READ_ERROR", format!("Failed to read file: {}", e));
+            }
+            file_issues.list.sort_by_key(|issue| issue.line);
+            issues.append(&mut file_issues.list);
+        }
+        issues
+    }
+
+    fn write_issues<W: Write>(issues: &[Issue], writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "file\tline\tseverity\tcode\tmessage")?;
+        for issue in issues {
+            writeln!(writer, "{}", issue.to_tsv())?;
+        }
+        writer.flush()
+    }
+
+    /// Reads sequence lengths by ID from a FASTA/FASTQ file
+    fn sequence_lengths(path: &Path) -> Result<HashMap<String, usize>, String> {
+        read_seq_records(path)?
+            .map(|record| record.map(|r| (r.id, r.seq.len())))
+            .collect()
+    }
+
+    /// Checks FASTA headers and sequence lines
+    fn check_fasta(path: &Path, alphabet: Option<Alphabet>, issues: &mut Issues) -> io::Result<()> {
+        let mut ids = SeenIds::default();
+        let mut current: Option<SeqCheck> = None;
+
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line_no = index + 1;
+            let line = line?;
+            let line = line.trim_end();
+
+            if let Some(header) = line.strip_prefix('>') {
+                if let Some(record) = current.take() {
+                    record.finish(alphabet, issues);
+                }
+                let id = header.split_whitespace().next().unwrap_or_default().to_string();
+                if id.is_empty() {
+                    issues.error(line_no, "EMPTY_ID", "Header has no ID".to_string());
+                }
+                ids.check(&id, line_no, issues);
+                current = Some(SeqCheck::new(id, line_no));
+            } else if line.is_empty() {
+                continue;
+            } else {
+                match current.as_mut() {
+                    Some(record) => record.add_line(line.as_bytes(), line_no),
+                    None => issues.error(line_no, "NO_HEADER", "Sequence data before the first header".to_string()),
+                }
+            }
+        }
+        if let Some(record) = current {
+            record.finish(alphabet, issues);
+        }
+        Ok(())
+    }
+
+    /// Checks four-line FASTQ records
+    fn check_fastq(path: &Path, alphabet: Option<Alphabet>, issues: &mut Issues) -> io::Result<()> {
+        let mut ids = SeenIds::default();
+        let mut lines = BufReader::new(File::open(path)?).lines().enumerate();
+
+        while let Some((index, header)) = lines.next() {
+            let line_no = index + 1;
+            let header = header?;
+            let header = header.trim_end();
+            if header.is_empty() {
+                continue;
+            }
+            let Some(header) = header.strip_prefix('@') else {
+                issues.error(line_no, "BAD_HEADER", "Expected a record header starting with '@', stopped checking the file".to_string());
+                return Ok(());
+            };
+
+            let mut record_lines = Vec::with_capacity(3);
+            for _ in 0..3 {
+                match lines.next() {
+                    Some((_, line)) => record_lines.push(line?.trim_end().to_string()),
+                    None => break,
+                }
+            }
+            let [seq, plus, qual] = record_lines.as_slice() else {
+                issues.error(line_no, "TRUNCATED", "Record ends before its sequence, '+' and quality lines".to_string());
+                return Ok(());
+            };
+            if !plus.starts_with('+') {
+                issues.error(line_no + 2, "BAD_SEPARATOR", "Expected the '+' separator line, stopped checking the file".to_string());
+                return Ok(());
+            }
+
+            let id = header.split_whitespace().next().unwrap_or_default().to_string();
+            if id.is_empty() {
+                issues.error(line_no, "EMPTY_ID", "Header has no ID".to_string());
+            }
+            ids.check(&id, line_no, issues);
+
+            if seq.len() != qual.len() {
+                issues.error(line_no + 3, "QUAL_LENGTH", format!(
+                    "{}: quality length {} differs from sequence length {}", id, qual.len(), seq.len()
+                ));
+            }
+            if let Some(c) = qual.chars().find(|c| !('!'..='~').contains(c)) {
+                issues.error(line_no + 3, "QUAL_CHAR", format!("{}: invalid quality character {:?}", id, c));
+            }
+
+            let mut record = SeqCheck::new(id, line_no);
+            record.add_line(seq.as_bytes(), line_no + 1);
+            record.finish(alphabet, issues);
+        }
+        Ok(())
+    }
+
+    /// Checks GFF3/GTF columns, coordinates, phases and (GFF3) `Parent` references
+    fn check_gff(path: &Path, gtf: bool, lengths: Option<&HashMap<String, usize>>, issues: &mut Issues) -> io::Result<()> {
+        let mut feature_ids: HashSet<String> = HashSet::new();
+        let mut parents: Vec<(usize, String)> = Vec::new();
+        let mut unknown_seqs: HashSet<String> = HashSet::new();
+
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line_no = index + 1;
+            let line = line?;
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.starts_with("##FASTA") {
+                break;
+            }
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [seqname, _, feature_type, start, end, score, strand, phase, attributes] = fields.as_slice() else {
+                issues.error(line_no, "COLUMNS", format!("Expected 9 tab-separated columns, found {}", fields.len()));
+                continue;
+            };
+
+            match (start.parse::<u64>(), end.parse::<u64>()) {
+                (Ok(start), Ok(end)) => {
+                    if start == 0 || start > end {
+                        issues.error(line_no, "COORDINATE", format!("Invalid range {}..{}, start must be >= 1 and <= end", start, end));
+                    }
+                    match lengths.map(|l| l.get(*seqname)) {
+                        Some(Some(&len)) if end as usize > len => issues.error(line_no, "OUT_OF_RANGE", format!(
+                            "End {} is beyond the length {} of {}", end, len, seqname
+                        )),
+                        Some(None) if unknown_seqs.insert(seqname.to_string()) => issues.warning(line_no, "UNKNOWN_SEQUENCE", format!(
+                            "Sequence {} is not in the reference", seqname
+                        )),
+                        _ => {}
+                    }
+                }
+                _ => issues.error(line_no, "COORDINATE", format!("Start/end must be integers, found {:?} and {:?}", start, end)),
+            }
+
+            if *score != "." && score.parse::<f64>().is_err() {
+                issues.error(line_no, "SCORE", format!("Invalid score {:?}", score));
+            }
+            if !matches!(*strand, "+" | "-" | "." | "?") {
+                issues.error(line_no, "STRAND", format!("Invalid strand {:?}", strand));
+            }
+            match *phase {
+                "0" | "1" | "2" => {}
+                "." if *feature_type == "CDS" => issues.error(line_no, "PHASE", "CDS features require a phase of 0, 1 or 2".to_string()),
+                "." => {}
+                other => issues.error(line_no, "PHASE", format!("Invalid phase {:?}, expected 0, 1, 2 or '.'", other)),
+            }
+
+            let pairs = attributes.split(';').map(str::trim).filter(|a| !a.is_empty());
+            if gtf {
+                let keys: HashSet<&str> = pairs.filter_map(|a| a.split_whitespace().next()).collect();
+                for required in ["gene_id", "transcript_id"] {
+                    if !keys.contains(required) {
+                        issues.error(line_no, "MISSING_ATTRIBUTE", format!("GTF attribute {} is missing", required));
+                    }
+                }
+            } else {
+                for (key, value) in pairs.filter_map(|a| a.split_once('=')) {
+                    match key {
+                        "ID" => {
+                            feature_ids.insert(value.to_string());
+                        }
+                        "Parent" => parents.extend(value.split(',').map(|p| (line_no, p.to_string()))),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Parents may be defined after their children, so references are resolved at the end
+        for (line_no, parent) in parents {
+            if !feature_ids.contains(&parent) {
+                issues.error(line_no, "ORPHAN_PARENT", format!("Parent {} is not defined by any ID", parent));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Issue list of one file
+struct Issues {
+    file: PathBuf,
+    list: Vec<Issue>,
+}
+
+impl Issues {
+    fn push(&mut self, line: usize, severity: Severity, code: &'static str, message: String) {
+        self.list.push(Issue { file: self.file.clone(), line, severity, code, message });
+    }
+
+    fn error(&mut self, line: usize, code: &'static str, message: String) {
+        self.push(line, Severity::Error, code, message);
+    }
+
+    fn warning(&mut self, line: usize, code: &'static str, message: String) {
+        self.push(line, Severity::Warning, code, message);
+    }
+}
+
+/// IDs of a file with the line they were first seen on
+#[derive(Default)]
+struct SeenIds(HashMap<String, usize>);
+
+impl SeenIds {
+    fn check(&mut self, id: &str, line_no: usize, issues: &mut Issues) {
+        if id.is_empty() {
+            return;
+        }
+        match self.0.get(id) {
+            Some(first) => issues.error(line_no, "DUPLICATE_ID", format!("{} was already used at line {}", id, first)),
+            None => {
+                self.0.insert(id.to_string(), line_no);
+            }
+        }
+    }
+}
+
+/// Sequence checks accumulated over the lines of one record
+struct SeqCheck {
+    id: String,
+    line: usize,
+    len: usize,
+    first_invalid: [Option<(usize, char)>; 3], // First character outside each of `Alphabet::ALL`
+    detector: AlphabetDetector,
+}
+
+impl SeqCheck {
+    fn new(id: String, line: usize) -> Self {
+        Self { id, line, len: 0, first_invalid: [None; 3], detector: AlphabetDetector::default() }
+    }
+
+    fn add_line(&mut self, residues: &[u8], line_no: usize) {
+        self.len += residues.len();
+        self.detector.add(residues);
+        for (alphabet, first) in Alphabet::ALL.iter().zip(self.first_invalid.iter_mut()) {
+            if first.is_none() {
+                *first = residues.iter()
+                    .find(|&&c| !alphabet.contains(c))
+                    .map(|&c| (line_no, c as char));
+            }
+        }
+    }
+
+    fn finish(self, alphabet: Option<Alphabet>, issues: &mut Issues) {
+        if self.len == 0 {
+            issues.error(self.line, "EMPTY_SEQUENCE", format!("{} has an empty sequence", self.id));
+            return;
+        }
+
+        let invalid = match alphabet {
+            Some(alphabet) => {
+                let index = Alphabet::ALL.iter().position(|a| *a == alphabet).unwrap_or(0);
+                self.first_invalid[index].map(|found| (found, alphabet.name()))
+            }
+            // Without a declared alphabet the sequence only has to fit one of them
+            None if self.first_invalid.iter().all(Option::is_some) => {
+                self.first_invalid[2].map(|found| (found, "any"))
+            }
+            None => None,
+        };
+        if let Some(((line_no, c), name)) = invalid {
+            issues.error(line_no, "ILLEGAL_CHAR", format!("{}: character {:?} is not valid for the {} alphabet", self.id, c, name));
+            return;
+        }
+
+        match (alphabet, self.detector.finish()) {
+            (Some(alphabet), Some((detected, confidence))) if detected != alphabet && confidence >= Alphabet::MIN_CONFIDENCE => {
+                issues.warning(self.line, "ALPHABET_MISMATCH", format!("{} looks like {} ({:.2}), not {}", self.id, detected.name(), confidence, alphabet.name()));
+            }
+            (None, Some((detected, confidence))) if confidence < Alphabet::MIN_CONFIDENCE => {
+                issues.warning(self.line, "LOW_CONFIDENCE", format!("{} is ambiguous, best guess {} ({:.2})", self.id, detected.name(), confidence));
+            }
+            _ => {}
+        }
+    }
+}
+